@@ -0,0 +1,119 @@
+extern crate colored;
+
+mod smew;
+
+pub use self::smew::{ error, source, lexer, parser, interpreter, linter };
+
+pub use self::smew::error::{ SmewError, ErrorKind, Severity, Response };
+pub use self::smew::source::{ Source, FilePath };
+pub use self::smew::lexer::{ Lexer, Token, TokenType, Pos };
+pub use self::smew::parser::{ Parser, Statement, StatementNode, Expression, ExpressionNode };
+pub use self::smew::interpreter::{ Interpreter, ForeignFunction, CallContext, Signature, Kind, Object, Record, Function, MAX_DEPTH };
+pub use self::smew::linter::{ Lints, Lint, Level };
+
+use std::any::Any;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+
+
+// lexes and parses a whole source, reporting every problem found along the way
+pub fn parse(source: &Source) -> Result<Vec<Statement>, Vec<SmewError>> {
+  let (tokens, mut errors) = Lexer::default(source.chars(), source).tokenize();
+  let (ast, parse_errors)  = Parser::new(tokens, source).parse_partial();
+
+  errors.extend(parse_errors);
+
+  if errors.is_empty() {
+    Ok(ast)
+  } else {
+    Err(errors)
+  }
+}
+
+
+
+// the embedding entry point, everything bound by one run stays around for the next
+pub struct Smew {
+  interpreter: Interpreter,
+}
+
+impl Smew {
+  pub fn new() -> Self {
+    Smew {
+      interpreter: Interpreter::new(Rc::new(Source::from("<smew>", Vec::new()))),
+    }
+  }
+
+  pub fn with_foreign<F>(mut self, name: &str, signature: Signature, function: F) -> Self
+  where
+    F: FnMut(&mut CallContext, &[Object]) -> Result<Object, SmewError> + 'static
+  {
+    self.interpreter.add_foreign(name, signature, function);
+    self
+  }
+
+  pub fn with_data<T: Any>(mut self, value: T) -> Self {
+    self.interpreter.set_data(value);
+    self
+  }
+
+  pub fn with_search_path<T: Into<PathBuf>>(mut self, path: T) -> Self {
+    self.interpreter.add_search_path(path.into());
+    self
+  }
+
+  // how deep calls may go, which needs a stack to match
+  pub fn with_max_depth(mut self, depth: usize) -> Self {
+    self.interpreter.set_max_depth(depth);
+    self
+  }
+
+  pub fn run_str(&mut self, source: &str) -> Result<Record, Vec<SmewError>> {
+    self.run(Rc::new(Source::from("<string>", source.lines().map(|x| x.to_string()).collect())))
+  }
+
+  pub fn run_file(&mut self, path: &str) -> Result<Record, Vec<SmewError>> {
+    match Source::new(path.to_string()) {
+      Ok(source) => self.run(Rc::new(source)),
+      Err(error) => Err(vec!(SmewError::new(ErrorKind::Io, format!("can't read `{}`: {}", path, error)))),
+    }
+  }
+
+  pub fn run(&mut self, source: Rc<Source>) -> Result<Record, Vec<SmewError>> {
+    let ast = parse(&source)?;
+
+    self.run_ast(source, &ast).map_err(|error| vec!(error))
+  }
+
+  // for an already parsed source, say one that was linted first
+  pub fn run_ast(&mut self, source: Rc<Source>, ast: &[Statement]) -> Result<Record, SmewError> {
+    self.interpreter.set_source(source);
+    self.interpreter.evaluate(ast)
+  }
+
+  // runs a field of the record that was left for later, giving back what it evaluated to if it was
+  pub fn invoke(&mut self, record: &Record, field: &str, args: Vec<Object>) -> Result<Option<Object>, SmewError> {
+    self.interpreter.invoke(record, field, args)
+  }
+
+  // runs `when-<event>` on the record, giving back what it evaluated to if it's there
+  pub fn fire(&mut self, record: &Record, event: &str, args: Vec<Object>) -> Result<Option<Object>, SmewError> {
+    self.interpreter.fire(record, event, args)
+  }
+
+  // for when the high-level runs aren't enough
+  pub fn interpreter(&mut self) -> &mut Interpreter {
+    &mut self.interpreter
+  }
+
+  pub fn into_interpreter(self) -> Interpreter {
+    self.interpreter
+  }
+}
+
+impl Default for Smew {
+  fn default() -> Self {
+    Self::new()
+  }
+}
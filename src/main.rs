@@ -1,18 +1,20 @@
 extern crate colored;
-extern crate backtrace;
 extern crate rustyline;
+extern crate smew;
 
-mod smew;
+mod repl;
 
-use self::smew::source::*;
-use self::smew::lexer::*;
-use self::smew::parser::*;
-use self::smew::interpreter::*;
+use smew::{ parse, Smew, SmewError, ErrorKind, Severity, Source, Statement, Object, CallContext, Signature, Kind, Lints, Lint, Level };
+use smew::Response::Wrong;
 
-use std::collections::HashMap;
-use rustyline::Editor;
+use std::env;
+use std::process;
+use std::rc::Rc;
+use std::thread;
 
-fn print(args: &Vec<Object>) -> Object {
+use rustyline::DefaultEditor;
+
+fn print(_context: &mut CallContext, args: &[Object]) -> Result<Object, SmewError> {
   for arg in args {
     if let Some(ref string) = arg.to_str_object() {
       print!("{}", string)
@@ -21,93 +23,254 @@ fn print(args: &Vec<Object>) -> Object {
 
   println!();
 
-  Object::Nil
+  Ok(Object::Nil)
 }
 
-fn input(args: &Vec<Object>) -> Object {
-  let mut rl = Editor::<()>::new();
+fn input(context: &mut CallContext, _args: &[Object]) -> Result<Object, SmewError> {
+  let mut rl = match DefaultEditor::new() {
+    Ok(rl)     => rl,
+    Err(error) => return Err(context.error(format!("can't read input: {}", error))),
+  };
 
-  let mut result = String::new();
+  match rl.readline("") {
+    Ok(line) => Ok(Object::Str(line)),
+    _        => Ok(Object::Str(String::new())),
+  }
+}
 
-  let readline = rl.readline("");
+fn color(context: &mut CallContext, args: &[Object]) -> Result<Object, SmewError> {
+  use colored::{ Color, Colorize };
+
+  // the signature makes sure this is a string
+  let name = args[0].to_string();
+
+  let color = match name.parse::<Color>() {
+    Ok(color) => color,
+    Err(_)    => return Err(
+      context.error(
+        format!("no such color `{}`", name)
+      )
+    ),
+  };
+
+  let text = match args[1].to_str_object() {
+    Some(text) => text.to_string(),
+    None       => return Err(
+      context.error(
+        format!("can't color `{:?}`", args[1])
+      )
+    ),
+  };
+
+  Ok(Object::Str(format!("{}", text.color(color))))
+}
 
-  match readline {
-    Ok(line) => {
-      result = line;
-    },
+const USAGE: &str = "\
+usage: smew <command> [options] [file]
 
-    _ => (),
-  }
+commands:
+  run <file>    lex, parse, lint and evaluate a script
+  check <file>  lex, parse and lint a script without evaluating it
+  repl          evaluate lines interactively
+  help          show this message
+
+options for run and check:
+  --allow <lint>  don't report the lint
+  --warn <lint>   report the lint as a warning, the default
+  --deny <lint>   report the lint as an error
+
+lints:
+  unused-assignment, redefined-field, duplicate-parent, discarded-value, conflicting-field,
+  or all of them
+
+pass `-` as the file to read the script from stdin";
+
+// exit codes, so scripts and editors can tell which stage went wrong
+const EXIT_OK:      i32 = 0;
+const EXIT_LEXER:   i32 = 1;
+const EXIT_PARSER:  i32 = 2;
+const EXIT_RUNTIME: i32 = 3;
+const EXIT_LINT:    i32 = 4;
+const EXIT_USAGE:   i32 = 64;
+const EXIT_NOINPUT: i32 = 66;
 
-  Object::Str(result)
+// calls going too deep are an error the interpreter reports, given a stack with room to get that deep
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+
+
+fn load(path: &str) -> Result<Rc<Source>, i32> {
+  let source = if path == "-" {
+    Source::from_stdin()
+  } else {
+    Source::new(path.to_string())
+  };
+
+  source.map(Rc::new).map_err(|error| {
+    eprintln!("{}", Wrong(format!("can't read `{}`: {}", path, error)));
+
+    EXIT_NOINPUT
+  })
 }
 
-fn color(args: &Vec<Object>) -> Object {
-  use colored::Colorize;
+fn smew() -> Smew {
+  let mut smew = Smew::new()
+    .with_foreign("print", Signature::any(), print)
+    .with_foreign("color", Signature::new().param(Kind::Str).param(Kind::Any), color)
+    .with_foreign("input", Signature::new(), input);
 
-  let color = format!("{}", args[0].to_str_object().unwrap());
-  let text  = format!("{}", args[1].to_str_object().unwrap());
+  if let Some(paths) = env::var_os("SMEW_PATH") {
+    for path in env::split_paths(&paths) {
+      smew = smew.with_search_path(path)
+    }
+  }
 
-  Object::Str(format!("{}", text.color(color)))
+  smew
 }
 
-fn main() {
-  let test = r#"
-rectangle:
-  width:  100
-  height: 100
+// warnings are shown right away, denied lints stop the script like any other error
+fn lint(source: &Source, ast: &[Statement], lints: &Lints) -> Result<(), Vec<SmewError>> {
+  let (errors, warnings): (Vec<SmewError>, Vec<SmewError>) = lints.check(source, ast).into_iter().partition(|x| x.severity == Severity::Error);
+
+  for warning in warnings.iter() {
+    eprintln!("{}", warning)
+  }
 
-area = rectangle.width * rectangle.height
+  if errors.is_empty() {
+    Ok(())
+  } else {
+    Err(errors)
+  }
+}
 
-status1 = color("blue", "my fav area is") ++ color("red", "== ") ++ area
+fn run(source: &Rc<Source>, lints: &Lints) -> Result<(), Vec<SmewError>> {
+  let ast = parse(source)?;
 
-print()
-print(status1 ++ "\n")
+  lint(source, &ast, lints)?;
 
-brain:
-  thought: "i actually really like magenta"
+  smew().run_ast(source.clone(), &ast).map(|_| ()).map_err(|error| vec!(error))
+}
 
-print(color("magenta", brain.thought))
+fn check(source: &Rc<Source>, lints: &Lints) -> Result<(), Vec<SmewError>> {
+  let ast = parse(source)?;
 
-print("what's your thought on sushi?")
-your_thought_on_sushi = color("green", input())
+  lint(source, &ast, lints)
+}
 
-print()
+fn exit_code(error: &SmewError) -> i32 {
+  match error.kind {
+    ErrorKind::Io      => EXIT_NOINPUT,
+    ErrorKind::Lexer   => EXIT_LEXER,
+    ErrorKind::Parser  => EXIT_PARSER,
+    ErrorKind::Runtime => EXIT_RUNTIME,
+    ErrorKind::Lint    => EXIT_LINT,
+  }
+}
 
-print("your though on sushi:")
-print(your_thought_on_sushi)
-  "#;
 
-  let source = Source::from("<main>", test.lines().map(|x| x.into()).collect::<Vec<String>>());
-  let lexer  = Lexer::default(test.chars().collect(), &source);
 
-  let mut tokens = Vec::new();
+fn main() {
+  let smew = thread::Builder::new().stack_size(STACK_SIZE).spawn(start);
 
-  for token_result in lexer {
-    if let Ok(token) = token_result {
-      tokens.push(token)
-    } else {
-      return
-    }
+  match smew.map(|smew| smew.join()) {
+    Ok(Ok(())) => (),
+    _          => process::exit(EXIT_RUNTIME),
   }
+}
+
+fn start() {
+  let args = env::args().skip(1).collect::<Vec<String>>();
 
-  let mut parser  = Parser::new(tokens, &source);
+  let command: fn(&Rc<Source>, &Lints) -> Result<(), Vec<SmewError>> = match args.first().map(|x| x.as_str()) {
+    Some("run")   => run,
+    Some("check") => check,
 
-  match parser.parse() {
-    Ok(ref ast) => {
-      let mut foreign = HashMap::new();
+    Some("repl") if args.len() == 1 => {
+      process::exit(repl::repl(smew().into_interpreter()))
+    },
 
-      foreign.insert("print".to_string(), print as ForeignFunction);
-      foreign.insert("color".to_string(), color as ForeignFunction);
-      foreign.insert("input".to_string(), input as ForeignFunction);
+    Some("help") | Some("--help") | Some("-h") => {
+      println!("{}", USAGE);
+      process::exit(EXIT_OK)
+    },
 
-      let mut interpreter = Interpreter::new(&source, foreign);
+    Some(other) => {
+      eprintln!("{}", Wrong(format!("no such command `{}`", other)));
+      eprintln!("{}", USAGE);
+      process::exit(EXIT_USAGE)
+    },
+
+    None => {
+      eprintln!("{}", USAGE);
+      process::exit(EXIT_USAGE)
+    },
+  };
+
+  let mut lints = Lints::new();
+  let mut path  = None;
+
+  let mut rest = args.iter().skip(1);
+
+  while let Some(arg) = rest.next() {
+    let level = match arg.as_str() {
+      "--allow" => Level::Allow,
+      "--warn"  => Level::Warn,
+      "--deny"  => Level::Deny,
+
+      _ if path.is_none() => {
+        path = Some(arg);
+        continue
+      },
+
+      _ => {
+        eprintln!("{}", USAGE);
+        process::exit(EXIT_USAGE)
+      },
+    };
+
+    match rest.next().map(|x| x.as_str()) {
+      Some("all") => for &lint in Lint::ALL.iter() {
+        lints.set(lint, level)
+      },
+
+      Some(name) => match Lint::from_name(name) {
+        Some(lint) => lints.set(lint, level),
+        None       => {
+          eprintln!("{}", Wrong(format!("no such lint `{}`", name)));
+          process::exit(EXIT_USAGE)
+        },
+      },
+
+      None => {
+        eprintln!("{}", USAGE);
+        process::exit(EXIT_USAGE)
+      },
+    }
+  }
 
-      match interpreter.evaluate(ast) {
-        _ => (),
+  let path = match path {
+    Some(path) => path,
+    None       => {
+      eprintln!("{}", USAGE);
+      process::exit(EXIT_USAGE)
+    },
+  };
+
+  let source = match load(path) {
+    Ok(source) => source,
+    Err(code)  => process::exit(code),
+  };
+
+  let code = match command(&source, &lints) {
+    Ok(())     => EXIT_OK,
+    Err(errors) => {
+      for error in errors.iter() {
+        eprintln!("{}", error)
       }
+
+      exit_code(&errors[0])
     },
+  };
 
-    _ => ()
-  }
+  process::exit(code)
 }
@@ -0,0 +1,143 @@
+use smew::{ parse, Source, Statement, StatementNode, Interpreter, Object };
+use smew::Response::Wrong;
+
+use super::EXIT_OK;
+
+use std::env;
+use std::mem;
+use std::path::PathBuf;
+use std::rc::Rc;
+
+use rustyline::DefaultEditor;
+use rustyline::error::ReadlineError;
+
+const PROMPT:       &str = ">> ";
+const CONTINUATION: &str = ".. ";
+
+
+
+fn history_path() -> PathBuf {
+  let mut path = env::var_os("HOME").map(PathBuf::from).unwrap_or_default();
+  path.push(".smew_history");
+
+  path
+}
+
+// a trailing `:` opens a record body, which is read until an empty line
+fn opens_body(line: &str) -> bool {
+  line.trim_end().ends_with(':')
+}
+
+fn show(object: &Object) -> String {
+  match *object {
+    Object::Str(ref content) => format!("{:?}", content),
+
+    Object::Record(ref record) => {
+      let mut names = record.map.keys().cloned().collect::<Vec<String>>();
+      names.sort();
+
+      format!("<record: {}>", names.join(", "))
+    },
+
+    Object::Function(ref function) => format!("<function {}>", function.name),
+
+    Object::List(ref items) => format!("[{}]", items.iter().map(show).collect::<Vec<String>>().join(", ")),
+
+    ref object => object.to_str_object().map(|x| x.to_string()).unwrap_or_default(),
+  }
+}
+
+fn evaluate(interpreter: &mut Interpreter, lines: Vec<String>) {
+  let source = Rc::new(Source::from("<repl>", lines));
+
+  let ast = match parse(&source) {
+    Ok(ast)    => ast,
+    Err(errors) => {
+      for error in errors {
+        eprintln!("{}", error)
+      }
+
+      return
+    },
+  };
+
+  interpreter.set_source(source);
+
+  match interpreter.evaluate(&ast) {
+    Ok(record) => if let Some(&Statement { node: StatementNode::Expression(_), .. }) = ast.last() {
+      match record.content.last() {
+        Some(&Object::Nil) | None => (),
+        Some(value)               => println!("{}", show(value)),
+      }
+    },
+
+    Err(error) => eprintln!("{}", error),
+  }
+}
+
+
+
+pub fn repl(mut interpreter: Interpreter) -> i32 {
+  let mut editor = match DefaultEditor::new() {
+    Ok(editor) => editor,
+    Err(error) => {
+      eprintln!("{}", Wrong(format!("can't start repl: {}", error)));
+
+      return super::EXIT_USAGE
+    },
+  };
+
+  let history = history_path();
+
+  // there's simply no history the first time around
+  let _ = editor.load_history(&history);
+
+  let mut lines = Vec::new();
+
+  loop {
+    let prompt = if lines.is_empty() { PROMPT } else { CONTINUATION };
+
+    match editor.readline(prompt) {
+      Ok(line) => {
+        if !line.trim().is_empty() {
+          let _ = editor.add_history_entry(line.as_str());
+        }
+
+        if lines.is_empty() {
+          if line.trim().is_empty() {
+            continue
+          }
+
+          let opens = opens_body(&line);
+
+          lines.push(line);
+
+          if opens {
+            continue
+          }
+        } else if !line.trim().is_empty() {
+          lines.push(line);
+
+          continue
+        }
+
+        evaluate(&mut interpreter, mem::take(&mut lines))
+      },
+
+      Err(ReadlineError::Interrupted) => lines.clear(),
+      Err(ReadlineError::Eof)         => break,
+
+      Err(error) => {
+        eprintln!("{}", Wrong(format!("can't read line: {}", error)));
+
+        break
+      },
+    }
+  }
+
+  if let Err(error) = editor.save_history(&history) {
+    eprintln!("{}", Wrong(format!("can't save history to `{}`: {}", history.display(), error)))
+  }
+
+  EXIT_OK
+}
@@ -1,6 +1,9 @@
 use std::fmt;
 use colored::Colorize;
 
+use super::source::FilePath;
+use super::lexer::Pos;
+
 pub enum Response<T: fmt::Display> {
   Wrong(T),
   Weird(T),
@@ -9,16 +12,6 @@ pub enum Response<T: fmt::Display> {
 
 use self::Response::*;
 
-#[macro_export]
-macro_rules! response {
-  ( $( $r:expr ),+ ) => {{
-    $(
-      print!("{}", $r);
-    )*
-    println!();
-  }};
-}
-
 impl<T: fmt::Display> fmt::Display for Response<T> {
   fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
     let (color, message_type, message) = match *self {
@@ -34,4 +27,159 @@ impl<T: fmt::Display> fmt::Display for Response<T> {
 
     write!(f, "{}", message)
   }
-}
\ No newline at end of file
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+  Io,
+  Lexer,
+  Parser,
+  Runtime,
+  Lint,
+}
+
+impl fmt::Display for ErrorKind {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    use self::ErrorKind::*;
+
+    match *self {
+      Io      => write!(f, "io"),
+      Lexer   => write!(f, "lexer"),
+      Parser  => write!(f, "parser"),
+      Runtime => write!(f, "runtime"),
+      Lint    => write!(f, "lint"),
+    }
+  }
+}
+
+
+
+// warnings get reported without stopping anything
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+  Error,
+  Warning,
+}
+
+
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct SmewError {
+  pub kind:     ErrorKind,
+  pub severity: Severity,
+  pub message:  String,
+  pub file:     Option<FilePath>,
+  pub hints:    Vec<String>,
+
+  // boxed, as a position holds its whole line and errors are passed around a lot
+  pub pos: Option<Box<Pos>>,
+}
+
+impl SmewError {
+  pub fn new<T: Into<String>>(kind: ErrorKind, message: T) -> Self {
+    SmewError {
+      kind,
+      severity: Severity::Error,
+      message:  message.into(),
+      file:     None,
+      hints:    Vec::new(),
+
+      pos: None,
+    }
+  }
+
+  pub fn at<T: Into<String>>(kind: ErrorKind, message: T, file: &FilePath, pos: Pos) -> Self {
+    SmewError {
+      file: Some(file.clone()),
+      pos:  Some(Box::new(pos)),
+
+      .. Self::new(kind, message)
+    }
+  }
+
+  pub fn with_severity(mut self, severity: Severity) -> Self {
+    self.severity = severity;
+    self
+  }
+
+  pub fn with_hint<T: Into<String>>(mut self, hint: T) -> Self {
+    self.hints.push(hint.into());
+    self
+  }
+
+  // takes an `Option` as well, for hints that may not have come up with anything
+  pub fn with_hints<I: IntoIterator<Item = String>>(mut self, hints: I) -> Self {
+    self.hints.extend(hints);
+    self
+  }
+}
+
+// renders the error the way it's shown to people, colors and all
+impl fmt::Display for SmewError {
+  fn fmt (&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.severity {
+      Severity::Error   => write!(f, "{}", Wrong(&self.message))?,
+      Severity::Warning => write!(f, "{}", Weird(&self.message))?,
+    }
+
+    if let Some(ref file) = self.file {
+      write!(f, "{}", file)?
+    }
+
+    if let Some(ref pos) = self.pos {
+      write!(f, "{}", pos)?
+    }
+
+    for hint in self.hints.iter() {
+      write!(f, "{}", Note(hint))?
+    }
+
+    Ok(())
+  }
+}
+
+impl std::error::Error for SmewError {}
+
+
+
+// the candidate closest to a misspelled name, if any is close enough to be a likely typo
+pub fn did_you_mean<'a, I: IntoIterator<Item = &'a str>>(name: &str, candidates: I) -> Option<String> {
+  let limit = (name.chars().count() / 3).max(1);
+
+  candidates.into_iter()
+    .filter(|candidate| *candidate != name)
+    .map(|candidate| (distance(name, candidate), candidate))
+    .filter(|&(distance, _)| distance <= limit)
+    .min()
+    .map(|(_, candidate)| format!("did you mean `{}`?", candidate))
+}
+
+// edit distance counted in chars, where swapping two neighbours is a single typo
+fn distance(a: &str, b: &str) -> usize {
+  let a = a.chars().collect::<Vec<char>>();
+  let b = b.chars().collect::<Vec<char>>();
+
+  let mut table = vec!(vec!(0; b.len() + 1); a.len() + 1);
+
+  for (i, row) in table.iter_mut().enumerate() {
+    row[0] = i
+  }
+
+  table[0] = (0 ..= b.len()).collect();
+
+  for i in 1 ..= a.len() {
+    for j in 1 ..= b.len() {
+      let cost = if a[i - 1] == b[j - 1] { 0 } else { 1 };
+
+      table[i][j] = (table[i - 1][j] + 1).min(table[i][j - 1] + 1).min(table[i - 1][j - 1] + cost);
+
+      if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+        table[i][j] = table[i][j].min(table[i - 2][j - 2] + 1)
+      }
+    }
+  }
+
+  table[a.len()][b.len()]
+}
@@ -0,0 +1,242 @@
+use std::any::Any;
+use std::cell::RefCell;
+use std::fmt;
+use std::rc::Rc;
+
+use super::super::error::SmewError;
+use super::*;
+
+// anything callable from smew, closures holding on to host state included
+pub type ForeignFunction = Box<dyn FnMut(&mut CallContext, &[Object]) -> Result<Object, SmewError>>;
+
+// a registered foreign function, along with what it accepts
+pub struct Foreign {
+  pub signature: Signature,
+  pub function:  Rc<RefCell<ForeignFunction>>,
+}
+
+
+
+// the kinds of value a foreign function can ask for
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+  Number,
+  Str,
+  Bool,
+  Record,
+  Function,
+  List,
+  Nil,
+  Any,
+}
+
+impl Kind {
+  pub fn of(object: &Object) -> Self {
+    match *object {
+      Object::Number(_)   => Kind::Number,
+      Object::Str(_)      => Kind::Str,
+      Object::Bool(_)     => Kind::Bool,
+      Object::Record(_)   => Kind::Record,
+      Object::Function(_) => Kind::Function,
+      Object::List(_)     => Kind::List,
+      Object::Nil         => Kind::Nil,
+    }
+  }
+
+  pub fn accepts(&self, object: &Object) -> bool {
+    *self == Kind::Any || *self == Kind::of(object)
+  }
+}
+
+impl fmt::Display for Kind {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    use self::Kind::*;
+
+    match *self {
+      Number   => write!(f, "a number"),
+      Str      => write!(f, "a string"),
+      Bool     => write!(f, "a boolean"),
+      Record   => write!(f, "a record"),
+      Function => write!(f, "a function"),
+      List     => write!(f, "a list"),
+      Nil      => write!(f, "nil"),
+      Any      => write!(f, "anything"),
+    }
+  }
+}
+
+
+
+// the parameters of a foreign function: required ones, then optional ones, then maybe any number more
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Signature {
+  pub required: Vec<Kind>,
+  pub optional: Vec<Kind>,
+  pub variadic: Option<Kind>,
+}
+
+impl Signature {
+  pub fn new() -> Self {
+    Signature::default()
+  }
+
+  // takes whatever it's given
+  pub fn any() -> Self {
+    Signature::new().variadic(Kind::Any)
+  }
+
+  pub fn param(mut self, kind: Kind) -> Self {
+    self.required.push(kind);
+    self
+  }
+
+  pub fn optional(mut self, kind: Kind) -> Self {
+    self.optional.push(kind);
+    self
+  }
+
+  pub fn variadic(mut self, kind: Kind) -> Self {
+    self.variadic = Some(kind);
+    self
+  }
+
+  fn arity(&self) -> String {
+    let (min, max) = (self.required.len(), self.required.len() + self.optional.len());
+
+    // the number that ends up next to "argument" decides the plural
+    let (count, last) = if self.variadic.is_some() {
+      (format!("at least {}", min), min)
+    } else if min == max {
+      (format!("{}", min), min)
+    } else {
+      (format!("{} to {}", min, max), max)
+    };
+
+    format!("{} argument{}", count, if last == 1 { "" } else { "s" })
+  }
+
+  // the message, and the offending argument if there's one to point at
+  pub fn check(&self, name: &str, args: &[Object]) -> Result<(), (Option<usize>, String)> {
+    let max = self.required.len() + self.optional.len();
+
+    if args.len() < self.required.len() || self.variadic.is_none() && args.len() > max {
+      return Err((None, format!("`{}` takes {} but was given {}", name, self.arity(), args.len())))
+    }
+
+    let kinds = self.required.iter().chain(self.optional.iter()).chain(self.variadic.iter().cycle());
+
+    for (index, (kind, arg)) in kinds.zip(args).enumerate() {
+      if !kind.accepts(arg) {
+        return Err((Some(index), format!("argument {} of `{}` should be {}, not {}", index + 1, name, kind, Kind::of(arg))))
+      }
+    }
+
+    Ok(())
+  }
+}
+
+// what a foreign function gets to see of the interpreter calling it
+pub struct CallContext<'c> {
+  interpreter: &'c mut Interpreter,
+  name:        &'c str,
+  pos:         &'c Pos,
+}
+
+impl<'c> CallContext<'c> {
+  pub fn new(interpreter: &'c mut Interpreter, name: &'c str, pos: &'c Pos) -> Self {
+    CallContext {
+      interpreter,
+      name,
+      pos,
+    }
+  }
+
+  // the name the function was called by
+  pub fn name(&self) -> &str {
+    self.name
+  }
+
+  pub fn pos(&self) -> &Pos {
+    self.pos
+  }
+
+  // an error pointing at the call
+  pub fn error<T: Into<String>>(&self, message: T) -> SmewError {
+    self.interpreter.error(message, self.pos)
+  }
+
+  // looks a binding up the same way the calling code would
+  pub fn lookup(&self, name: &str) -> Option<Object> {
+    self.interpreter.lookup_name(name)
+  }
+
+  // calls a smew function value, like one handed over as an argument
+  pub fn call(&mut self, function: &Object, args: Vec<Object>) -> Result<Object, SmewError> {
+    match *function {
+      Object::Function(ref function) => match self.interpreter.call_function(function, args, self.pos)? {
+        Object::Record(record) => Ok(record.into_object()),
+        other                  => Ok(other),
+      },
+
+      ref other => Err(
+        self.error(
+          format!("can't call {}", Kind::of(other))
+        )
+      ),
+    }
+  }
+
+  // host state registered on the interpreter with `set_data`
+  pub fn data<T: Any>(&self) -> Option<&T> {
+    self.interpreter.data()
+  }
+
+  pub fn data_mut<T: Any>(&mut self) -> Option<&mut T> {
+    self.interpreter.data_mut()
+  }
+
+  pub fn interpreter(&mut self) -> &mut Interpreter {
+    self.interpreter
+  }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn message(signature: Signature, args: &[Object]) -> Option<String> {
+    signature.check("bump", args).err().map(|(_, message)| message)
+  }
+
+  #[test]
+  fn arity() {
+    let two = [Object::Nil, Object::Nil];
+
+    assert_eq!(message(Signature::new().param(Kind::Any), &two).unwrap(), "`bump` takes 1 argument but was given 2");
+    assert_eq!(message(Signature::new().optional(Kind::Any), &two).unwrap(), "`bump` takes 0 to 1 argument but was given 2");
+    assert_eq!(message(Signature::new(), &two).unwrap(), "`bump` takes 0 arguments but was given 2");
+    assert_eq!(message(Signature::new().param(Kind::Any).variadic(Kind::Any), &[]).unwrap(), "`bump` takes at least 1 argument but was given 0");
+    assert_eq!(message(Signature::new().param(Kind::Any).param(Kind::Any).variadic(Kind::Any), &[]).unwrap(), "`bump` takes at least 2 arguments but was given 0");
+    assert_eq!(message(Signature::new().optional(Kind::Any).optional(Kind::Any), &[Object::Nil, Object::Nil, Object::Nil]).unwrap(), "`bump` takes 0 to 2 arguments but was given 3");
+  }
+
+  #[test]
+  fn kinds() {
+    let signature = Signature::new().param(Kind::Number).variadic(Kind::Str);
+
+    assert_eq!(message(signature.clone(), &[Object::Number(1.0), Object::Str("a".into()), Object::Str("b".into())]), None);
+
+    assert_eq!(
+      signature.check("bump", &[Object::Number(1.0), Object::Str("a".into()), Object::Bool(true)]),
+      Err((Some(2), "argument 3 of `bump` should be a string, not a boolean".to_string()))
+    );
+  }
+
+  #[test]
+  fn any_takes_anything() {
+    assert_eq!(message(Signature::any(), &[]), None);
+    assert_eq!(message(Signature::any(), &[Object::Nil, Object::List(Vec::new())]), None);
+  }
+}
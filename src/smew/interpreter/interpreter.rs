@@ -1,49 +1,230 @@
+use std::any::{ Any, TypeId };
+use std::cell::RefCell;
 use std::collections::HashMap;
+use std::fmt;
+use std::mem;
+use std::path::{ Path, PathBuf };
+use std::rc::{ Rc, Weak };
 
-use super::super::error::Response::Wrong;
+use super::super::error::{ SmewError, ErrorKind, did_you_mean };
 use super::*;
 
+// bindings are shared between copies of a frame, so functions keep seeing the scope they were declared in
+#[derive(Clone)]
 pub struct Frame {
-  pub locals: HashMap<String, Object>,
+  pub locals: Rc<RefCell<HashMap<String, Object>>>,
+  pub block:  bool,
 }
 
 impl Frame {
   pub fn new() -> Self {
+    Frame::with(HashMap::new())
+  }
+
+  pub fn with(locals: HashMap<String, Object>) -> Self {
     Frame {
-      locals: HashMap::new()
+      locals: Rc::new(RefCell::new(locals)),
+      block:  false,
     }
   }
 
-  pub fn set_name(&mut self, name: &String, value: Object) {
-    self.locals.insert(name.clone(), value);
+  // the frame of a loop body, which assigns through to bindings of the surrounding scope
+  pub fn block() -> Self {
+    Frame {
+      block: true,
+
+      .. Frame::new()
+    }
   }
 
-  pub fn find_name(&self, name: &str) -> Option<&Object> {
-    self.locals.get(name)
+  pub fn set_name(&self, name: &str, value: Object) {
+    self.locals.borrow_mut().insert(name.to_owned(), value);
+  }
+
+  pub fn find_name(&self, name: &str) -> Option<Object> {
+    self.locals.borrow().get(name).cloned()
+  }
+
+  pub fn names(&self) -> Vec<String> {
+    self.locals.borrow().keys().cloned().collect()
+  }
+
+  pub fn capture(&self) -> Captured {
+    Captured {
+      locals: Rc::downgrade(&self.locals),
+      block:  self.block,
+    }
   }
 }
 
+// frames are the same when they share bindings, and are shown by name since a function's
+// frames can hold the function itself
+impl PartialEq for Frame {
+  fn eq(&self, other: &Self) -> bool {
+    Rc::ptr_eq(&self.locals, &other.locals) && self.block == other.block
+  }
+}
+
+impl fmt::Debug for Frame {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    let mut names = self.names();
+
+    names.sort();
 
-pub type ForeignFunction = fn(&Vec<Object>) -> Object;
+    write!(f, "Frame({})", names.join(", "))
+  }
+}
+
+impl Default for Frame {
+  fn default() -> Self {
+    Self::new()
+  }
+}
+
+// a frame as a function holds on to it. functions are usually bound in the very frames they close
+// over, so the interpreter is the one keeping those alive, lest neither is ever freed
+#[derive(Clone)]
+pub struct Captured {
+  locals: Weak<RefCell<HashMap<String, Object>>>,
+  block:  bool,
+}
+
+impl Captured {
+  pub fn frame(&self) -> Option<Frame> {
+    self.locals.upgrade().map(|locals| Frame { locals, block: self.block })
+  }
+}
+
+impl PartialEq for Captured {
+  fn eq(&self, other: &Self) -> bool {
+    self.locals.ptr_eq(&other.locals) && self.block == other.block
+  }
+}
+
+impl fmt::Debug for Captured {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    match self.frame() {
+      Some(frame) => write!(f, "Captured({:?})", frame),
+      None        => write!(f, "Captured(gone)"),
+    }
+  }
+}
+
+
+// statements that leave a body early, passed up until a record, function or loop handles them
+enum Flow {
+  Return(Object),
+  Break,
+  Continue,
+}
+
+// how deep calls go before giving up, which the main thread's stack has room for in release builds.
+// debug builds take much more stack per call
+pub const MAX_DEPTH: usize = 500;
+
+// the record `self` stands for, along with the frame its body declares fields into
+struct Receiver {
+  record: Record,
+  frame:  usize,
+}
+
+// what a call ends up calling, foreign functions only ever being reached by name
+enum Callee<'a> {
+  Smew(Function),
+  Foreign(&'a str),
+}
 
-pub struct Interpreter<'a> {
+pub struct Interpreter {
   stack: Vec<Frame>,
-  source: &'a Source,
-  foreign: HashMap<String, ForeignFunction>,
+  source: Rc<Source>,
+  foreign: HashMap<String, Foreign>,
+  data:    HashMap<TypeId, Box<dyn Any>>,
+
+  flow: Option<Flow>,
+
+  // whether records declared right now are fields of another record, which may be left for later
+  declaring_fields: bool,
+  receivers:        Vec<Receiver>,
+
+  search_path: Vec<PathBuf>,
+  importing:   Vec<PathBuf>,
+  modules:     HashMap<PathBuf, Record>,
+
+  // the frames functions were declared in, by where their bindings live
+  captured: HashMap<*const RefCell<HashMap<String, Object>>, Frame>,
+
+  // how many function calls and fields left for later are running inside each other
+  depth:     usize,
+  max_depth: usize,
 }
 
-impl<'a> Interpreter<'a> {
-  pub fn new(source: &'a Source, foreign: HashMap<String, ForeignFunction>) -> Self {
+impl Interpreter {
+  pub fn new(source: Rc<Source>) -> Self {
     Interpreter {
       stack: vec!(Frame::new()),
       source,
-      foreign
+      foreign: HashMap::new(),
+      data:    HashMap::new(),
+
+      flow: None,
+
+      declaring_fields: false,
+      receivers:        Vec::new(),
+
+      search_path: Vec::new(),
+      importing:   Vec::new(),
+      modules:     HashMap::new(),
+
+      captured: HashMap::new(),
+
+      depth:     0,
+      max_depth: MAX_DEPTH,
     }
   }
 
+  // calls are checked against the signature before the function ever sees them
+  pub fn add_foreign<F>(&mut self, name: &str, signature: Signature, function: F)
+  where
+    F: FnMut(&mut CallContext, &[Object]) -> Result<Object, SmewError> + 'static
+  {
+    let foreign = Foreign {
+      signature,
+      function: Rc::new(RefCell::new(Box::new(function))),
+    };
+
+    self.foreign.insert(name.to_string(), foreign);
+  }
+
+  // host state for foreign functions to share, one value per type
+  pub fn set_data<T: Any>(&mut self, value: T) {
+    self.data.insert(TypeId::of::<T>(), Box::new(value));
+  }
+
+  pub fn data<T: Any>(&self) -> Option<&T> {
+    self.data.get(&TypeId::of::<T>()).and_then(|value| value.downcast_ref())
+  }
+
+  pub fn data_mut<T: Any>(&mut self) -> Option<&mut T> {
+    self.data.get_mut(&TypeId::of::<T>()).and_then(|value| value.downcast_mut())
+  }
+
+  // directories searched for imports that aren't found next to the importing file
+  pub fn add_search_path(&mut self, path: PathBuf) {
+    self.search_path.push(path)
+  }
+
+  pub fn set_max_depth(&mut self, depth: usize) {
+    self.max_depth = depth
+  }
+
+  // lets one interpreter, and everything bound in it, outlive the source it started with
+  pub fn set_source(&mut self, source: Rc<Source>) {
+    self.source = source
+  }
 
 
-  pub fn evaluate(&mut self, ast: &Vec<Statement>) -> Result<Record, ()> {
+
+  pub fn evaluate(&mut self, ast: &[Statement]) -> Result<Record, SmewError> {
     use self::StatementNode::*;
 
     let mut content = Vec::new();
@@ -52,80 +233,690 @@ impl<'a> Interpreter<'a> {
     for statement in ast.iter() {
       match statement.node {
         self::StatementNode::Record(ref name, ref parents, ref body) => {
-          self.stack.push(Frame::new());
+          let record = if name.starts_with("when-") {
+            if !parents.is_empty() {
+              return Err(
+                self.error(
+                  format!("handlers like `{}` can't inherit", name),
+                  &statement.pos
+                )
+              )
+            }
 
-          let mut inherited_map = HashMap::new();
+            self::Record::deferred(Rc::new(body.clone()))
+          } else if self.declaring_fields && parents.is_empty() && !is_data(body) {
+            // fields that do things only do them when they're read
+            self::Record::deferred(Rc::new(body.clone()))
+          } else {
+            self.stack.push(Frame::new());
 
-          for parent in parents {
-            let parent_record = self.evaluate_expression(&parent)?;
+            let record = self.evaluate_record(parents, body, &statement.pos);
 
-            if let Object::Record(record) = parent_record {
-              for (name, value) in record.map.iter() {
+            self.stack.pop();
 
-                inherited_map.insert(name.clone(), value.clone());
-                
-                let value = if value.map.len() == 0 && record.content.len() == 1 {
-                  record.content[0].clone()
-                } else {
-                  Object::Record((*value).clone())
-                };
+            record?
+          };
 
-                self.set_binding(name, value.clone());
-              }
-            } else {
-              return Err(
-                response!(
-                  Wrong("can't inherit from non-record"),
-                  self.source.file,
-                  statement.pos
-                )
-              )
+          let value = record.clone().into_object();
+
+          if self.declaring_fields {
+            let frame = self.body_frame();
+
+            if let Some(receiver) = self.receivers.last_mut().filter(|receiver| receiver.frame == frame) {
+              receiver.record.map.insert(name.to_owned(), record.clone());
+            }
+          }
+
+          map.insert(name.to_owned(), record);
+
+          self.set_binding(name, value);
+        },
+
+        Function(ref name, ref params, ref body) => {
+          for frame in self.stack.iter() {
+            self.captured.entry(Rc::as_ptr(&frame.locals)).or_insert_with(|| frame.clone());
+          }
+
+          let scope    = self.stack.iter().map(Frame::capture).collect();
+          let function = self::Function::new(name.clone(), params.clone(), body.clone(), scope);
+
+          self.set_binding(name, Object::Function(function))
+        },
+
+        Import(ref path, ref name) => {
+          let module = self.import(path, &statement.pos)?;
+
+          match *name {
+            Some(ref name) => match module.map.get(name) {
+              Some(record) => self.set_binding(name, record.clone().into_object()),
+              None         => return Err(
+                self.error(
+                  format!("no such thing as `{}` in `{}`", name, path),
+                  &statement.pos
+                ).with_hints(did_you_mean(name, module.map.keys().map(String::as_str)))
+              ),
+            },
+
+            None => {
+              let name = Path::new(path).file_stem().map(|x| x.to_string_lossy().into_owned()).unwrap_or_default();
+
+              self.set_binding(&name, Object::Record(module))
+            },
+          }
+        },
+
+        If(ref branches, ref otherwise) => {
+          let mut chosen = otherwise.as_ref();
+
+          for (condition, body) in branches {
+            if self.evaluate_condition(condition)? {
+              chosen = Some(body);
+
+              break
             }
           }
 
-          let mut record = self.evaluate(body)?;
+          // branches run in the surrounding scope, so their fields become the record's own
+          if let Some(body) = chosen {
+            let record = self.evaluate(body)?;
+
+            content.extend(record.content);
+            map.extend(record.map);
+          }
+        },
 
-          record.map.extend(inherited_map);
+        Assignment(ref name, ref right) => {
+          let right = self.evaluate_expression(right)?;
 
-          let value = if record.map.len() == 0 && record.content.len() == 1 {
-            record.content[0].clone()
-          } else {
-            Object::Record(record.clone())
+          self.set_binding(name, right);
+        },
+
+        Expression(ref expression) => {
+          let expression = self.evaluate_expression(expression)?;
+
+          content.push(expression)
+        },
+
+        Return(ref expression) => {
+          let value = match *expression {
+            Some(ref expression) => self.evaluate_expression(expression)?,
+            None                 => Object::Nil,
           };
 
-          map.insert(name.to_owned(), record);
+          self.flow = Some(Flow::Return(value))
+        },
+
+        Error => return Err(
+          self.error(
+            "can't evaluate a statement that failed to parse",
+            &statement.pos
+          )
+        ),
+
+        Break    => self.flow = Some(Flow::Break),
+        Continue => self.flow = Some(Flow::Continue),
+
+        For(ref names, ref iterable, ref body) => {
+          let items = self.iterate(names, iterable)?;
+
+          self.stack.push(Frame::block());
+
+          let record = self.evaluate_for(names, items, body);
+
           self.stack.pop();
 
-          self.set_binding(name, value);          
-        },
+          let record = record?;
+
+          content.extend(record.content);
+          map.extend(record.map);
+        },
+
+        While(ref condition, ref body) => {
+          self.stack.push(Frame::block());
+
+          let record = self.evaluate_while(condition, body);
+
+          self.stack.pop();
+
+          let record = record?;
+
+          content.extend(record.content);
+          map.extend(record.map);
+        },
+      }
+
+      if self.flow.is_some() {
+        break
+      }
+    }
+
+    Ok(self::Record::new(content, map))
+  }
+
+  fn evaluate_record(&mut self, parents: &[Expression], body: &[Statement], pos: &Pos) -> Result<Record, SmewError> {
+    let mut parent_records = Vec::new();
+
+    for parent in parents {
+      match self.evaluate_expression(parent)? {
+        Object::Record(record) => parent_records.push(record),
+
+        _ => return Err(
+          self.error(
+            "can't inherit from non-record",
+            pos
+          )
+        ),
+      }
+    }
+
+    // ancestors are ordered by which records they are, a grandparent inherited twice being the same one both times
+    let mut known = HashMap::new();
+
+    for parent in parent_records.iter() {
+      for ancestor in Some(Rc::new(parent.clone())).into_iter().chain(parent.ancestors.iter().cloned()) {
+        known.entry(ancestor.id).or_insert(ancestor);
+      }
+    }
+
+    let ids = parent_records.iter().map(|parent| parent.id).collect::<Vec<usize>>();
+
+    let ancestors = match linearize(&ids, |id| known[id].ancestor_ids()) {
+      Some(order) => order.iter().map(|id| known[id].clone()).collect::<Vec<Rc<Record>>>(),
+      None            => return Err(
+        self.error(
+          "can't order the parents so that each comes before the records it inherits from",
+          pos
+        ).with_hint("a parent can't be listed before another parent that inherits from it")
+      ),
+    };
+
+    // a field comes from the first record in that order declaring it, so an override beats what it overrides
+    let mut inherited_map = HashMap::new();
+
+    for ancestor in ancestors.iter() {
+      for (name, field) in ancestor.map.iter() {
+        if ancestor.declares(name) && !inherited_map.contains_key(name) {
+          inherited_map.insert(name.clone(), field.clone());
+        }
+      }
+    }
+
+    for (name, field) in inherited_map.iter() {
+      self.set_binding(name, field.clone().into_object());
+    }
+
+    // records at the top, and records that inherit, are `self` to everything in them. other records are
+    // fields, sharing `self` and `super` with the record around them
+    let receiver = !self.declaring_fields || !parents.is_empty();
+
+    if receiver {
+      let frame = self.stack.len() - 1;
+
+      self.receivers.push(Receiver { record: Record::new(Vec::new(), inherited_map.clone()), frame });
+      self.current_frame_mut().set_name("super", Object::Record(Record::new(Vec::new(), inherited_map.clone())));
+    }
+
+    let fields = mem::replace(&mut self.declaring_fields, true);
+
+    let record = self.evaluate(body);
+
+    self.declaring_fields = fields;
+
+    if receiver {
+      self.receivers.pop();
+    }
+
+    let returned = self.take_returned();
+    let mut record = record?;
+
+    // fields left for later get to see everything their record body bound, `super` included
+    let mut scope = self.current_frame().locals.borrow().clone();
+
+    if let Some(parents) = self.lookup_name("super") {
+      scope.insert("super".to_string(), parents);
+    }
+
+    let scope = Rc::new(scope);
+
+    for field in record.map.values_mut() {
+      if let Some(ref mut deferred) = field.deferred {
+        deferred.scope = scope.clone()
+      }
+    }
+
+    record.value = returned.map(Box::new);
+
+    // the record's own fields win over anything inherited
+    for (name, field) in inherited_map {
+      if !record.map.contains_key(&name) {
+        record.inherited.insert(name.clone());
+        record.map.insert(name, field);
+      }
+    }
+
+    record.ancestors = ancestors;
+
+    Ok(record)
+  }
+
+  // runs a field that was left for later, in the scope it was declared in, with the record's
+  // fields and the arguments on top. nothing happens for fields that are plain data
+  pub fn invoke(&mut self, record: &Record, field: &str, args: Vec<Object>) -> Result<Option<Object>, SmewError> {
+    match record.map.get(field).and_then(|field| field.deferred.clone()) {
+      Some(deferred) => self.run_deferred(&deferred, Some(record), args, &deferred.body[0].pos).map(Some),
+      None           => Ok(None),
+    }
+  }
+
+  // runs a body left for later in the scope it was declared in, with the record it's run on as `self`
+  fn run_deferred(&mut self, deferred: &Deferred, owner: Option<&Record>, args: Vec<Object>, pos: &Pos) -> Result<Object, SmewError> {
+    self.descend(pos)?;
+
+    let scope = Frame::with((*deferred.scope).clone());
+    let frame = Frame::new();
+
+    if let Some(owner) = owner {
+      for (name, field) in owner.map.iter() {
+        frame.set_name(name, field.clone().into_object())
+      }
+    }
+
+    frame.set_name("args", Object::List(args));
+
+    self.stack.push(scope);
+    self.stack.push(frame);
+
+    if let Some(owner) = owner {
+      let frame = self.stack.len() - 1;
+
+      self.receivers.push(Receiver { record: owner.clone(), frame });
+    }
+
+    let fields = mem::replace(&mut self.declaring_fields, false);
+
+    let result = self.evaluate(&deferred.body);
+
+    self.declaring_fields = fields;
+
+    if owner.is_some() {
+      self.receivers.pop();
+    }
+
+    self.stack.pop();
+    self.stack.pop();
+
+    self.depth -= 1;
+
+    let returned = self.take_returned();
+    let mut result = result?;
+
+    result.value = returned.map(Box::new);
+
+    Ok(result.into_object())
+  }
+
+  // runs the record's handler for an event, like `when-awake` for `awake`, if it has one
+  pub fn fire(&mut self, record: &Record, event: &str, args: Vec<Object>) -> Result<Option<Object>, SmewError> {
+    self.invoke(record, &format!("when-{}", event), args)
+  }
+
+  // the parser keeps `break` and `continue` inside loops, so only a `return` can get this far
+  fn take_returned(&mut self) -> Option<Object> {
+    match self.flow.take() {
+      Some(Flow::Return(value)) => Some(value),
+      _                         => None,
+    }
+  }
+
+  // what a `for` loop walks over, one list of bound values per iteration
+  fn iterate(&mut self, names: &[String], iterable: &Expression) -> Result<Vec<Vec<Object>>, SmewError> {
+    let value = self.evaluate_expression(iterable)?;
+
+    let items: Vec<Vec<Object>> = match value {
+      Object::Number(n) if n.fract() == 0.0 && n >= 0.0 => (0 .. n as usize).map(|i| vec!(Object::Number(i as f64))).collect(),
+
+      Object::List(items) => items.into_iter().enumerate().map(|(i, item)| {
+        if names.len() == 2 {
+          vec!(Object::Number(i as f64), item)
+        } else {
+          vec!(item)
+        }
+      }).collect(),
+
+      Object::Record(record) => {
+        let mut fields = record.map.clone().into_iter().collect::<Vec<(String, Record)>>();
+
+        fields.sort_by(|a, b| a.0.cmp(&b.0));
+
+        let mut items = Vec::new();
+
+        for (name, field) in fields {
+          if names.len() == 2 {
+            let value = self.read_field(&field, &name, Some(&record), &iterable.pos)?;
+
+            items.push(vec!(Object::Str(name), value))
+          } else {
+            items.push(vec!(Object::Str(name)))
+          }
+        }
+
+        items
+      },
+
+      other => return Err(
+        self.error(
+          format!("can't loop over {}, only whole numbers, lists and records", describe(&other)),
+          &iterable.pos
+        )
+      ),
+    };
+
+    if let Some(values) = items.first() {
+      if values.len() != names.len() {
+        return Err(
+          self.error(
+            format!("can't bind {} names when looping over numbers", names.len()),
+            &iterable.pos
+          )
+        )
+      }
+    }
+
+    Ok(items)
+  }
+
+  fn evaluate_for(&mut self, names: &[String], items: Vec<Vec<Object>>, body: &[Statement]) -> Result<Record, SmewError> {
+    let mut result = self::Record::new(Vec::new(), HashMap::new());
+
+    for values in items {
+      for (name, value) in names.iter().zip(values) {
+        self.current_frame_mut().set_name(name, value)
+      }
+
+      let record = self.evaluate(body)?;
+
+      result.content.extend(record.content);
+      result.map.extend(record.map);
+
+      if self.end_iteration() {
+        break
+      }
+    }
+
+    Ok(result)
+  }
+
+  fn evaluate_while(&mut self, condition: &Expression, body: &[Statement]) -> Result<Record, SmewError> {
+    let mut result = self::Record::new(Vec::new(), HashMap::new());
+
+    while self.evaluate_condition(condition)? {
+      let record = self.evaluate(body)?;
+
+      result.content.extend(record.content);
+      result.map.extend(record.map);
+
+      if self.end_iteration() {
+        break
+      }
+    }
+
+    Ok(result)
+  }
+
+  // settles whatever left the loop body, and whether the loop is done with
+  fn end_iteration(&mut self) -> bool {
+    match self.flow.take() {
+      Some(Flow::Break)    => true,
+      Some(Flow::Continue) => false,
+      None                 => false,
+
+      Some(Flow::Return(value)) => {
+        self.flow = Some(Flow::Return(value));
+
+        true
+      },
+    }
+  }
+
+  pub(super) fn call_function(&mut self, function: &Function, args: Vec<Object>, pos: &Pos) -> Result<Object, SmewError> {
+    if function.params.len() != args.len() {
+      return Err(
+        self.error(
+          format!("`{}` takes {} argument{} but was given {}", function.name, function.params.len(), if function.params.len() == 1 { "" } else { "s" }, args.len()),
+          pos
+        )
+      )
+    }
+
+    let frame = Frame::new();
+
+    for (param, arg) in function.params.iter().zip(args) {
+      frame.set_name(param, arg)
+    }
+
+    // the body sees where the function was declared, none of the caller's bindings
+    let scope = match function.scope.iter().map(Captured::frame).collect::<Option<Vec<Frame>>>() {
+      Some(scope) => scope,
+      None        => return Err(
+        self.error(
+          format!("`{}` can't be called, the interpreter it was declared in is gone", function.name),
+          pos
+        )
+      ),
+    };
+
+    self.descend(pos)?;
+
+    let outer_stack = mem::replace(&mut self.stack, scope);
+
+    self.stack.push(frame);
+
+    let fields    = mem::replace(&mut self.declaring_fields, false);
+    let receivers = mem::take(&mut self.receivers);
+
+    let record = self.evaluate(&function.body);
+
+    self.declaring_fields = fields;
+    self.receivers        = receivers;
+
+    self.stack = outer_stack;
+    self.depth -= 1;
+
+    let returned = self.take_returned();
+    let mut record = record?;
+
+    record.value = returned.map(Box::new);
+
+    Ok(Object::Record(record))
+  }
+
+  fn call_foreign(&mut self, name: &str, args: &[Expression], arg_values: Vec<Object>, pos: &Pos) -> Result<Object, SmewError> {
+    let foreign  = &self.foreign[name];
+    let function = foreign.function.clone();
+
+    if let Err((index, message)) = foreign.signature.check(name, &arg_values) {
+      let pos = index.map(|index| &args[index].pos).unwrap_or(pos);
+
+      return Err(
+        self.error(
+          message,
+          pos
+        )
+      )
+    }
+
+    let mut function = match function.try_borrow_mut() {
+      Ok(function) => function,
+      Err(_)       => return Err(
+        self.error(
+          format!("`{}` can't be called again while it's still running", name),
+          pos
+        )
+      ),
+    };
+
+    (*function)(&mut CallContext::new(self, name, pos), &arg_values)
+  }
+
+  fn resolve_import(&self, path: &str) -> Option<PathBuf> {
+    let here = Path::new(&self.source.file.0).parent().map(Path::to_path_buf).unwrap_or_default();
+
+    let mut names = vec!(PathBuf::from(path));
+
+    if Path::new(path).extension().is_none() {
+      names.push(PathBuf::from(format!("{}.smew", path)))
+    }
+
+    for directory in Some(&here).into_iter().chain(self.search_path.iter()) {
+      for name in names.iter() {
+        let candidate = directory.join(name);
+
+        if candidate.is_file() {
+          return candidate.canonicalize().ok()
+        }
+      }
+    }
+
+    None
+  }
+
+  fn import(&mut self, path: &str, pos: &Pos) -> Result<Record, SmewError> {
+    let resolved = match self.resolve_import(path) {
+      Some(resolved) => resolved,
+      None           => {
+        let mut error = self.error(format!("can't find `{}` to import", path), pos);
+
+        if self.search_path.is_empty() {
+          error = error.with_hint("only the importing file's directory was searched")
+        } else {
+          let directories = self.search_path.iter().map(|x| format!("`{}`", x.display())).collect::<Vec<String>>();
+
+          error = error.with_hint(format!("also searched {}", directories.join(", ")))
+        }
+
+        return Err(error)
+      },
+    };
+
+    if let Some(module) = self.modules.get(&resolved) {
+      return Ok(module.clone())
+    }
+
+    // the file we started from isn't on the import stack yet, but importing it again is a cycle all the same
+    let mut importing = self.importing.clone();
+
+    if importing.is_empty() {
+      importing.extend(Path::new(&self.source.file.0).canonicalize())
+    }
+
+    if importing.contains(&resolved) {
+      let cycle = importing.iter()
+        .skip_while(|x| **x != resolved)
+        .chain(Some(&resolved))
+        .map(|x| x.display().to_string())
+        .collect::<Vec<String>>();
+
+      return Err(
+        self.error(
+          format!("import cycle: {}", cycle.join(" -> ")),
+          pos
+        )
+      )
+    }
+
+    let source = match Source::new(resolved.display().to_string()) {
+      Ok(source) => Rc::new(source),
+      Err(error) => return Err(
+        self.error(
+          format!("can't import `{}`: {}", path, error),
+          pos
+        )
+      ),
+    };
+
+    let (tokens, mut errors) = Lexer::default(source.chars(), &source).tokenize();
+    let (ast, parse_errors)  = Parser::new(tokens, &source).parse_partial();
+
+    errors.extend(parse_errors);
+
+    if !errors.is_empty() {
+      let others = errors.len() - 1;
+      let error  = errors.remove(0);
+
+      return Err(
+        if others > 0 {
+          error.with_hint(format!("`{}` has {} more error{}", path, others, if others == 1 { "" } else { "s" }))
+        } else {
+          error
+        }
+      )
+    }
+
+    // modules are evaluated in isolation, seeing none of the importer's bindings
+    let outer_source = mem::replace(&mut self.source, source);
+    let outer_stack  = mem::replace(&mut self.stack, vec!(Frame::new()));
+    let outer_fields = mem::replace(&mut self.declaring_fields, false);
+    let outer_selves = mem::take(&mut self.receivers);
+
+    let depth = self.importing.len();
+
+    self.importing = importing;
+    self.importing.push(resolved.clone());
+
+    let record = self.evaluate(&ast);
 
-        Assignment(ref name, ref right) => {
-          let right = self.evaluate_expression(right)?;
+    self.importing.truncate(depth);
 
-          self.set_binding(name, right);
-        },
+    let frame = mem::replace(&mut self.stack, outer_stack).pop().unwrap();
 
-        Expression(ref expression) => {
-          let expression = self.evaluate_expression(expression)?;
+    self.declaring_fields = outer_fields;
+    self.receivers        = outer_selves;
 
-          content.push(expression)
-        }
+    self.source = outer_source;
 
-        _ => (),
-      }
+    let mut module = record?;
+
+    // top-level assignments and functions are reachable from outside too
+    for (name, value) in frame.locals.borrow().iter() {
+      module.map.entry(name.clone()).or_insert_with(|| self::Record::new(vec!(value.clone()), HashMap::new()));
     }
 
-    Ok(self::Record::new(content, map))
+    self.modules.insert(resolved, module.clone());
+
+    Ok(module)
+  }
+
+  fn evaluate_condition(&mut self, condition: &Expression) -> Result<bool, SmewError> {
+    match self.evaluate_expression(condition)? {
+      Object::Bool(value) => Ok(value),
+      other               => Err(
+        self.error(
+          format!("condition must be a boolean, found {}", describe(&other)),
+          &condition.pos
+        )
+      ),
+    }
   }
 
-  pub fn evaluate_expression(&mut self, expression: &Expression) -> Result<Object, ()> {
+  pub fn evaluate_expression(&mut self, expression: &Expression) -> Result<Object, SmewError> {
     use self::ExpressionNode::*;
 
     let result = match expression.node {
       Number(ref n)     => Object::Number(*n),
       Str(ref n)        => Object::Str(n.clone()),
       Bool(ref n)       => Object::Bool(*n),
-      Identifier(ref n) => self.find_name(n, &expression.pos)?,
+      Identifier(ref n) if n == "self" && !self.receivers.is_empty() => Object::Record(self.receivers.last().unwrap().record.clone()),
+
+      // naming a field that does things runs it, the same as reading it off its record
+      Identifier(ref n) => match self.find_name(n, &expression.pos)? {
+        Object::Record(ref field) if field.deferred.is_some() => {
+          let owner = self.receivers.last().map(|receiver| receiver.record.clone());
+
+          self.read_field(field, n, owner.as_ref(), &expression.pos)?
+        },
+
+        other => other,
+      },
 
       Neg(ref expression) => {
         let value = self.evaluate_expression(expression)?;
@@ -134,10 +925,9 @@ impl<'a> Interpreter<'a> {
           Object::Number(-a)
         } else {
           return Err(
-            response!(
-              Wrong("can't negate non-number"),
-              self.source.file,
-              expression.pos
+            self.error(
+              "can't negate non-number",
+              &expression.pos
             )
           )
         }
@@ -150,78 +940,198 @@ impl<'a> Interpreter<'a> {
           Object::Bool(!a)
         } else {
           return Err(
-            response!(
-              Wrong("can't flip non-boolean"),
-              self.source.file,
-              expression.pos
+            self.error(
+              "can't flip non-boolean",
+              &expression.pos
             )
           )
         }
       },
 
-      Call(ref expression, ref args) => {
-        let mut result     = Object::Nil;
+      Call(ref callee, ref args) => {
+        let function = match callee.node {
+          ExpressionNode::Identifier(ref name) => match self.lookup_name(name) {
+            Some(Object::Function(function)) => Callee::Smew(function),
+
+            _ if self.foreign.contains_key(name) => Callee::Foreign(name),
+
+            Some(other) => return Err(
+              self.error(
+                format!("can't call `{}`, it's {}", name, Kind::of(&other)),
+                &callee.pos
+              )
+            ),
+
+            None => return Err(
+              self.error(
+                format!("no such function `{}`", name),
+                &callee.pos
+              ).with_hints(did_you_mean(name, self.foreign.keys().map(String::as_str).chain(self.visible_names().iter().map(String::as_str))))
+            ),
+          },
+
+          _ => match self.evaluate_expression(callee)? {
+            Object::Function(function) => Callee::Smew(function),
+
+            other => return Err(
+              self.error(
+                format!("can't call {}", Kind::of(&other)),
+                &callee.pos
+              )
+            ),
+          },
+        };
+
         let mut arg_values = Vec::new();
 
         for arg in args {
           arg_values.push(self.evaluate_expression(arg)?)
         }
-        
-        if let ExpressionNode::Identifier(ref name) = expression.node {
-          if let Some(func) = self.foreign.get(name) {
 
-            result = func(&arg_values)
+        match function {
+          Callee::Smew(function) => self.call_function(&function, arg_values, &callee.pos)?,
+          Callee::Foreign(name)  => self.call_foreign(name, args, arg_values, &callee.pos)?,
+        }
+      },
+
+      // `field!` runs the field on `self` and `record.field!` on the record, handlers included.
+      // fields that are only data are just their value
+      Invoke(ref target) => {
+        let (invoked, owner) = self.invoked(target)?;
+
+        match invoked {
+          Object::Record(Record { deferred: Some(ref deferred), .. }) => self.run_deferred(deferred, owner.as_ref(), Vec::new(), &expression.pos)?,
+
+          other => other,
+        }
+      },
+
+      Field(ref record, ref name) => {
+        let (field, owner) = self.access(record, name, &expression.pos)?;
+
+        self.read_field(&field, name, Some(&owner), &expression.pos)?
+      },
+
+      Interpolation(ref parts) => {
+        let mut string = String::new();
+
+        for part in parts {
+          let value = self.evaluate_expression(part)?;
+
+          match value.to_str_object() {
+            Some(text) => string.push_str(&text.to_string()),
+            None       => return Err(
+              self.error(
+                format!("can't put {} into a string", Kind::of(&value)),
+                &part.pos
+              )
+            ),
           }
         }
 
-        result
+        Object::Str(string)
+      },
+
+      List(ref items) => {
+        let mut values = Vec::new();
+
+        for item in items {
+          values.push(self.evaluate_expression(item)?)
+        }
+
+        Object::List(values)
+      },
+
+      If(ref condition, ref then, ref otherwise) => {
+        if self.evaluate_condition(condition)? {
+          self.evaluate_expression(then)?
+        } else {
+          self.evaluate_expression(otherwise)?
+        }
+      },
+
+      Subscript(ref list, ref index) => {
+        let list_value  = self.evaluate_expression(list)?;
+        let index_value = self.evaluate_expression(index)?;
+
+        let items = match list_value {
+          Object::List(items) => items,
+          other               => return Err(
+            self.error(
+              format!("can't index into {}, only lists", describe(&other)),
+              &list.pos
+            )
+          ),
+        };
+
+        let offset = match index_value {
+          Object::Number(n) if n.fract() == 0.0 => n as i64,
+          other                                 => return Err(
+            self.error(
+              format!("list index must be a whole number, found {}", describe(&other)),
+              &index.pos
+            )
+          ),
+        };
+
+        // negative indices count from the back, `-1` being the last item
+        let position = if offset < 0 { offset + items.len() as i64 } else { offset };
+
+        if position < 0 || position >= items.len() as i64 {
+          return Err(
+            self.error(
+              format!("index {} is out of range for list of length {}", offset, items.len()),
+              &index.pos
+            )
+          )
+        }
+
+        items.into_iter().nth(position as usize).unwrap()
       },
 
       Binary(ref a, ref op, ref b) => {
         use self::Operator::*;
         use self::Object::*;
 
-        let a_value = self.evaluate_expression(&a)?;
-
-        if let Record(ref record) = a_value {
-          if *op == Index {
-            if let ExpressionNode::Identifier(ref index) = b.node {
-              if let Some(ref object) = record.map.get(index) {
-
-                let record = (**object).clone();
-
-                let result = if record.map.len() == 0 && record.content.len() == 1 {
-                  record.content[0].clone()
-                } else {
-                  Object::Record(record)
-                };
-
-                return Ok(result)
-              
-              } else {
-                return Err(
-                  response!(
-                    Wrong(format!("no such field `{}` on record", index)),
-                    self.source.file,
-                    expression.pos
-                  )
+        let a_value = self.evaluate_expression(a)?;
+        let b_value = self.evaluate_expression(b)?;
+
+        match (&a_value, op, &b_value) {
+          (Number(a), Add, Number(b))  => Object::Number(a + b),
+          (Number(a), Sub, Number(b))  => Object::Number(a - b),
+          (Number(a), Mul, Number(b))  => Object::Number(a * b),
+          (&Number(_), Div, &Number(b)) | (&Number(_), IntDiv, &Number(b)) | (&Number(_), Mod, &Number(b)) if b == 0.0 => return Err(
+            self.error(
+              format!("can't divide {} by zero", describe(&a_value)),
+              &expression.pos
+            )
+          ),
+
+          (Number(a), Div, Number(b))    => Object::Number(a / b),
+          (Number(a), IntDiv, Number(b)) => Object::Number((a / b).floor()),
+
+          // the remainder takes the sign of the divisor, so `-1 % 10` wraps around to `9`
+          (Number(a), Mod, Number(b)) => Object::Number(a - b * (a / b).floor()),
+
+          (Number(a), Pow, Number(b)) => {
+            let result = a.powf(*b);
+
+            if !result.is_finite() {
+              return Err(
+                self.error(
+                  format!("`{} ^ {}` has no finite result", a, b),
+                  &expression.pos
                 )
-              }
+              )
             }
-          }
-        }
 
-        let b_value = self.evaluate_expression(&b)?;
+            Object::Number(result)
+          },
 
-        match (&a_value, op, &b_value) {
-          (&Number(ref a), Add, &Number(ref b))  => Object::Number(a + b),
-          (&Number(ref a), Sub, &Number(ref b))  => Object::Number(a - b),
-          (&Number(ref a), Mul, &Number(ref b))  => Object::Number(a * b),
-          (&Number(ref a), Div, &Number(ref b))  => Object::Number(a / b),
-          (&Number(ref a), Lt, &Number(ref b))   => Object::Bool(a < b),
-          (&Number(ref a), Gt, &Number(ref b))   => Object::Bool(a > b),
-          (&Number(ref a), LtEq, &Number(ref b)) => Object::Bool(a <= b),
-          (&Number(ref a), GtEq, &Number(ref b)) => Object::Bool(a >= b),
+          (Number(a), Lt, Number(b))   => Object::Bool(a < b),
+          (Number(a), Gt, Number(b))   => Object::Bool(a > b),
+          (Number(a), LtEq, Number(b)) => Object::Bool(a <= b),
+          (Number(a), GtEq, Number(b)) => Object::Bool(a >= b),
 
           (&Bool(a), And, &Bool(b)) => Object::Bool(a && b),
           (&Bool(a), Or, &Bool(b))  => Object::Bool(a || b),
@@ -229,7 +1139,9 @@ impl<'a> Interpreter<'a> {
           (ref a, Eq, ref b)   => Object::Bool(a == b),
           (ref a, NEq, ref b)  => Object::Bool(a != b),
 
-          (ref a, Concat, ref b) => {
+          (List(a), Concat, List(b)) => Object::List(a.iter().chain(b.iter()).cloned().collect()),
+
+          (a, Concat, b) => {
             
             if let Some(ref a) = a.to_str_object() {
               if let Some(ref b) = b.to_str_object() {
@@ -238,19 +1150,17 @@ impl<'a> Interpreter<'a> {
             }
 
             return Err(
-              response!(
-                Wrong(format!("can't perform operation `{:?}{}{:?}`", a_value, op, b_value)),
-                self.source.file,
-                expression.pos
+              self.error(
+                format!("can't use `{}` on {} and {}", op, describe(&a_value), describe(&b_value)),
+                &expression.pos
               )
             )
           }
 
           _ => return Err(
-            response!(
-              Wrong(format!("can't perform operation `{:?}{}{:?}`", a_value, op, b_value)),
-              self.source.file,
-              expression.pos
+            self.error(
+              format!("can't use `{}` on {} and {}", op, describe(&a_value), describe(&b_value)),
+              &expression.pos
 
             )
           )
@@ -260,10 +1170,8 @@ impl<'a> Interpreter<'a> {
       _ => Object::Nil,
     };
 
-    if let Object::Record(ref record) = result {
-      if record.map.len() == 0 && record.content.len() == 1 {
-        return Ok(record.content[0].clone())
-      }
+    if let Object::Record(record) = result {
+      return Ok(record.into_object())
     }
 
     Ok(result)
@@ -271,6 +1179,28 @@ impl<'a> Interpreter<'a> {
 
 
 
+  // a call going one deeper, which is an error rather than a blown stack once it's too deep
+  fn descend(&mut self, pos: &Pos) -> Result<(), SmewError> {
+    if self.depth >= self.max_depth {
+      return Err(
+        self.error(
+          format!("calls go more than {} deep", self.max_depth),
+          pos
+        ).with_hint("something keeps calling itself without ever stopping")
+      )
+    }
+
+    self.depth += 1;
+
+    Ok(())
+  }
+
+  pub(super) fn error<T: Into<String>>(&self, message: T, pos: &Pos) -> SmewError {
+    SmewError::at(ErrorKind::Runtime, message, &self.source.file, pos.clone())
+  }
+
+
+
   fn current_frame_mut(&mut self) -> &mut Frame {
     self.stack.last_mut().unwrap()
   }
@@ -281,29 +1211,454 @@ impl<'a> Interpreter<'a> {
 
 
 
-  fn set_binding(&mut self, name: &String, value: Object) {
-    self.current_frame_mut().set_name(name, value)
-  }
+  fn set_binding(&mut self, name: &str, value: Object) {
+    let mut target = self.stack.len() - 1;
 
-  fn find_name(&self, name: &str, pos: &Pos) -> Result<Object, ()> {
-    let mut parent_offset = self.stack.len() - 1;
-    
-    loop {
-      if let Some(ref object) = self.stack[parent_offset].find_name(name) {
-        return Ok((**object).clone())
-      } else {
-        if parent_offset == 0 {
-          return Err(
-            response!(
-              Wrong(format!("no such thing as `{}`", name)),
-              self.source.file,
-              pos
-            )
-          )
-        }
+    for (offset, frame) in self.stack.iter().enumerate().rev() {
+      if frame.find_name(name).is_some() {
+        target = offset;
+
+        break
+      }
 
-        parent_offset -= 1;
+      if !frame.block {
+        break
       }
     }
+
+    self.stack[target].set_name(name, value)
+  }
+
+  fn field<'r>(&self, record: &'r Record, index: &str, pos: &Pos) -> Result<&'r Record, SmewError> {
+    match record.map.get(index) {
+      Some(field) => Ok(field),
+      None        => Err(
+        self.error(
+          format!("no such field `{}` on record", index),
+          pos
+        ).with_hints(did_you_mean(index, record.map.keys().map(String::as_str)))
+      ),
+    }
+  }
+
+  // what reading a field gives, running it first if it does things. handlers only ever run when fired
+  fn read_field(&mut self, field: &Record, name: &str, owner: Option<&Record>, pos: &Pos) -> Result<Object, SmewError> {
+    match field.deferred {
+      Some(ref deferred) if !name.starts_with("when-") => self.run_deferred(deferred, owner, Vec::new(), pos),
+      _                                                => Ok(field.clone().into_object()),
+    }
+  }
+
+  // a field off a record, along with the record it runs on. the parents' version of a field still runs on `self`
+  fn access(&mut self, record: &Expression, name: &str, pos: &Pos) -> Result<(Record, Record), SmewError> {
+    let outer = match record.node {
+      ExpressionNode::Identifier(ref name) if name == "super" => self.receivers.last().map(|receiver| receiver.record.clone()),
+      _                                                      => None,
+    };
+
+    let record = match self.evaluate_expression(record)? {
+      Object::Record(record) => record,
+      other                  => return Err(
+        self.error(
+          format!("can't read `{}` off {}, only records have fields", name, describe(&other)),
+          pos
+        )
+      ),
+    };
+
+    let field = self.field(&record, name, pos)?.clone();
+
+    Ok((field, outer.unwrap_or(record)))
+  }
+
+  // what `target!` runs and what it runs on, a field being taken as it is rather than read
+  fn invoked(&mut self, target: &Expression) -> Result<(Object, Option<Record>), SmewError> {
+    let receiver = self.receivers.last().map(|receiver| receiver.record.clone());
+
+    match target.node {
+      ExpressionNode::Identifier(ref name) => Ok((self.find_name(name, &target.pos)?, receiver)),
+
+      ExpressionNode::Field(ref record, ref name) => {
+        let (field, owner) = self.access(record, name, &target.pos)?;
+
+        Ok((field.into_object(), Some(owner)))
+      },
+
+      _ => Ok((self.evaluate_expression(target)?, receiver)),
+    }
+  }
+
+  // the frame a body declares into, past the frames of any loops it's in
+  fn body_frame(&self) -> usize {
+    self.stack.iter().rposition(|frame| !frame.block).unwrap_or(0)
+  }
+
+  pub(super) fn lookup_name(&self, name: &str) -> Option<Object> {
+    self.stack.iter().rev().find_map(|frame| frame.find_name(name))
+  }
+
+  fn find_name(&self, name: &str, pos: &Pos) -> Result<Object, SmewError> {
+    match self.lookup_name(name) {
+      Some(object) => Ok(object),
+      None         => Err(
+        self.error(
+          format!("no such thing as `{}`", name),
+          pos
+        ).with_hints(did_you_mean(name, self.visible_names().iter().map(String::as_str)))
+      )
+    }
+  }
+
+  // every name bound somewhere on the stack, shadowed ones included
+  fn visible_names(&self) -> Vec<String> {
+    self.stack.iter().flat_map(Frame::names).collect()
+  }
+}
+
+
+
+// how a value shows up in an error, written out if it can be and by its kind otherwise
+fn describe(object: &Object) -> String {
+  match *object {
+    Object::Str(ref text) => format!("`\"{}\"`", text),
+    Object::Nil           => Kind::of(object).to_string(),
+
+    _ => match object.to_str_object() {
+      Some(text) => format!("`{}`", text),
+      None       => Kind::of(object).to_string(),
+    },
+  }
+}
+
+// whether a body only describes data, so that evaluating it once is as good as any number of times.
+// records in it are left out, since they're judged on their own
+fn is_data(body: &[Statement]) -> bool {
+  use self::StatementNode::*;
+
+  body.iter().all(|statement| match statement.node {
+    Expression(ref expression) | Assignment(_, ref expression) => !expression.calls(),
+
+    Return(ref expression) => expression.iter().all(|expression| !expression.calls()),
+
+    If(ref branches, ref otherwise) => {
+      branches.iter().all(|(condition, body)| !condition.calls() && is_data(body)) && otherwise.iter().all(|body| is_data(body))
+    },
+
+    For(_, ref expression, ref body) | While(ref expression, ref body) => !expression.calls() && is_data(body),
+
+    Import(..) => false,
+
+    _ => true,
+  })
+}
+
+
+
+#[cfg(test)]
+mod tests {
+  use std::cell::Cell;
+
+  use super::*;
+  use crate::Smew;
+
+  fn run(smew: &mut Smew, code: &str) -> Result<Record, String> {
+    smew.run_str(code).map_err(|errors| errors[0].message.clone())
+  }
+
+  // what the top-level record `name` stands for after running the code
+  fn value(code: &str, name: &str) -> Object {
+    run(&mut Smew::new(), code).unwrap().map[name].clone().into_object()
+  }
+
+  fn error(code: &str) -> String {
+    run(&mut Smew::new(), code).unwrap_err()
+  }
+
+  // a directory of its own holding the files, for the test called `name`
+  fn files(name: &str, files: &[(&str, &str)]) -> PathBuf {
+    let directory = std::env::temp_dir().join(format!("smew-{}-{}", name, std::process::id()));
+
+    for &(path, code) in files {
+      let path = directory.join(path);
+
+      std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+      std::fs::write(path, code).unwrap();
+    }
+
+    directory
+  }
+
+  fn run_file(smew: &mut Smew, path: PathBuf) -> Result<Record, String> {
+    smew.run_file(&path.display().to_string()).map_err(|errors| errors[0].message.clone())
+  }
+
+  #[test]
+  fn empty_scripts() {
+    assert!(run(&mut Smew::new(), "").unwrap().map.is_empty());
+    assert!(run(&mut Smew::new(), "\n\n").unwrap().map.is_empty());
+  }
+
+  #[test]
+  fn empty_strings() {
+    assert_eq!(value("x:\n  \"\" ++ \" \" ++ \"\"\n", "x"), Object::Str(" ".into()));
+  }
+
+  #[test]
+  fn functions_see_where_they_were_declared() {
+    let code = "scale = 2\ndef grow(x):\n  return x * scale\nbox:\n  scale = 100\n  size: grow(3)\nout:\n  box.size\n";
+
+    assert_eq!(value(code, "out"), Object::Number(6.0));
+  }
+
+  #[test]
+  fn functions_see_later_declarations_and_themselves() {
+    let code = "def a(n):\n  return b(n)\ndef b(n):\n  if n < 2:\n    return 1\n  return n * a(n - 1)\nout:\n  a(5)\n";
+
+    assert_eq!(value(code, "out"), Object::Number(120.0));
+  }
+
+  #[test]
+  fn functions_keep_their_scope_after_it_ends() {
+    let code = "def counter():\n  count = 10\n  def get():\n    return count\n  return get\nget = counter()\nout:\n  get()\n";
+
+    assert_eq!(value(code, "out"), Object::Number(10.0));
+  }
+
+  #[test]
+  fn functions_let_go_of_their_scope_with_the_interpreter() {
+    let kept  = Rc::new(RefCell::new(Vec::new()));
+    let store = kept.clone();
+
+    let mut smew = Smew::new().with_foreign("keep", Signature::new().param(Kind::Function), move |_, args| {
+      store.borrow_mut().push(args[0].clone());
+      Ok(Object::Nil)
+    });
+
+    run(&mut smew, "def f():\n  return 1\nkeep(f)\n").unwrap();
+
+    drop(smew);
+
+    let kept = kept.borrow()[0].clone();
+
+    match kept {
+      Object::Function(ref function) => assert!(function.scope.iter().all(|frame| frame.frame().is_none())),
+      ref other                      => panic!("kept {:?}", other),
+    }
+  }
+
+  #[test]
+  fn calls_that_go_too_deep() {
+    let mut smew = Smew::new().with_max_depth(10);
+
+    assert_eq!(run(&mut smew, "def f(n):\n  return f(n + 1)\nf(1)\n").unwrap_err(), "calls go more than 10 deep");
+    assert_eq!(run(&mut smew, "a:\n  down:\n    return self.down! + 1\nout:\n  a.down!\n").unwrap_err(), "calls go more than 10 deep");
+
+    // the depth is back down after the error
+    assert_eq!(run(&mut smew, "def g(n):\n  if n == 0:\n    return 0\n  return g(n - 1)\nout:\n  g(9)\n").unwrap().map["out"].clone().into_object(), Object::Number(0.0));
+  }
+
+  #[test]
+  fn self_and_super() {
+    let code = "foo:\n  size: 2\nfrog -> foo:\n  size: super.size * 2\n  looks:\n    width: self.size * 10\nout:\n  frog.looks.width\n";
+
+    assert_eq!(value(code, "out"), Object::Number(40.0));
+  }
+
+  #[test]
+  fn functions_do_not_see_the_callers_self() {
+    let code = "def label():\n  return self.name\nbox:\n  name: \"box\"\n  tag: label()\nout:\n  box.tag\n";
+
+    assert_eq!(error(code), "no such thing as `self`");
+  }
+
+  #[test]
+  fn fields_that_call_things_run_when_read() {
+    let ticks = Rc::new(Cell::new(0));
+    let count = ticks.clone();
+
+    let mut smew = Smew::new().with_foreign("tick", Signature::new(), move |_, _| {
+      count.set(count.get() + 1);
+      Ok(Object::Number(count.get() as f64))
+    });
+
+    let top = run(&mut smew, "box:\n  width: 3\n  area:\n    tick()\n    return width * 2\n").unwrap();
+
+    assert_eq!(ticks.get(), 0);
+
+    let area = smew.invoke(&top.map["box"], "area", Vec::new()).unwrap();
+
+    assert_eq!(area, Some(Object::Number(6.0)));
+    assert_eq!(ticks.get(), 1);
+  }
+
+  #[test]
+  fn fields_that_call_things_run_when_named_or_looped_over() {
+    let mut smew = Smew::new().with_foreign("id", Signature::new().param(Kind::Any), |_, args| Ok(args[0].clone()));
+
+    let code = "rect:\n  width: 3\n  area:\n    return id(width * 2)\n  total: area + 1\nsum = 0\nfor name, v in rect:\n  sum = sum + v\nout:\n  [rect.total, sum]\n";
+    let top  = run(&mut smew, code).unwrap();
+
+    assert_eq!(top.map["out"].clone().into_object(), Object::List(vec!(Object::Number(7.0), Object::Number(16.0))));
+  }
+
+  #[test]
+  fn arithmetic() {
+    assert_eq!(value("out:\n  -1 % 10\n", "out"), Object::Number(9.0));
+    assert_eq!(value("out:\n  2 ^ 3 ^ 2\n", "out"), Object::Number(512.0));
+    assert_eq!(value("out:\n  7 // 2\n", "out"), Object::Number(3.0));
+    assert_eq!(error("out:\n  1 / 0\n"), "can't divide `1` by zero");
+    assert_eq!(error("box:\n  x: 1\nout:\n  box + 1\n"), "can't use `+` on a record and `1`");
+  }
+
+  #[test]
+  fn conditions() {
+    let code = "def rank(lives):\n  if lives > 1:\n    return \"fine\"\n  elif lives == 1:\n    return \"careful\"\n  else:\n    return \"over\"\nout:\n  [rank(3), rank(1), rank(0), 100 if 1 < 2 else 10]\n";
+
+    assert_eq!(value(code, "out"), Object::List(vec!(Object::Str("fine".into()), Object::Str("careful".into()), Object::Str("over".into()), Object::Number(100.0))));
+  }
+
+  #[test]
+  fn branches_declare_fields_of_the_surrounding_record() {
+    assert_eq!(value("box:\n  if true:\n    size: 2\n  else:\n    size: 3\nout:\n  box.size\n", "out"), Object::Number(2.0));
+  }
+
+  #[test]
+  fn conditions_must_be_booleans() {
+    assert_eq!(error("if 1:\n  x = 2\n"), "condition must be a boolean, found `1`");
+    assert_eq!(error("out:\n  1 if \"yes\" else 2\n"), "condition must be a boolean, found `\"yes\"`");
+  }
+
+  #[test]
+  fn loops() {
+    let code = "point:\n  x: 1\n  y: 2\ntotal = 0\nfor i in 4:\n  total = total + i\nfor i, item in [10, 20]:\n  total = total + i * item\nnames = \"\"\nfor name in point:\n  names = names ++ name\nfor name, v in point:\n  total = total + v\nwhile total < 100:\n  total = total * 2\nout:\n  [total, names]\n";
+
+    assert_eq!(value(code, "out"), Object::List(vec!(Object::Number(116.0), Object::Str("xy".into()))));
+  }
+
+  #[test]
+  fn break_and_continue() {
+    let code = "odd = 0\nfor i in 10:\n  if i == 7:\n    break\n  if i % 2 == 0:\n    continue\n  odd = odd + i\nn = 0\nwhile true:\n  n = n + 1\n  if n == 3:\n    break\nout:\n  [odd, n]\n";
+
+    assert_eq!(value(code, "out"), Object::List(vec!(Object::Number(9.0), Object::Number(3.0))));
+  }
+
+  #[test]
+  fn names_bound_in_a_loop_stay_in_it() {
+    assert_eq!(error("for i in 2:\n  inner = i\nout:\n  inner\n"), "no such thing as `inner`");
+  }
+
+  #[test]
+  fn only_numbers_lists_and_records_are_looped_over() {
+    assert_eq!(error("for i in \"ab\":\n  x = i\n"), "can't loop over `\"ab\"`, only whole numbers, lists and records");
+  }
+
+  #[test]
+  fn lists_and_indexing() {
+    assert_eq!(value("out:\n  [[1, 2], [3]][0][1]\n", "out"), Object::Number(2.0));
+    assert_eq!(error("out:\n  1[0]\n"), "can't index into `1`, only lists");
+    assert_eq!(error("out:\n  [1][\"a\"]\n"), "list index must be a whole number, found `\"a\"`");
+  }
+
+  #[test]
+  fn fields_chain_with_calls_indexing_and_invoking() {
+    let code = "def double(x):\n  return x * 2\nbox:\n  w: 5\npath:\n  points: [1, 2, 3]\n  boxes: [box]\n  twice: double\n  n: 1\n  bump:\n    n = n + 1\n    return n\nout:\n  [path.points[1], path.boxes[0].w, path.twice(3), path.bump!]\n";
+
+    assert_eq!(value(code, "out"), Object::List(vec!(Object::Number(2.0), Object::Number(5.0), Object::Number(6.0), Object::Number(2.0))));
+  }
+
+  #[test]
+  fn fields_of_things_that_are_not_records() {
+    assert_eq!(error("x = 1\nout:\n  x.y\n"), "can't read `y` off `1`, only records have fields");
+  }
+
+  #[test]
+  fn imports() {
+    let directory = files("imports", &[
+      ("main.smew", "import \"lib/util\"\nimport box from \"lib/shapes.smew\"\nout:\n  [util.double(3), util.scale, box.w]\n"),
+      ("lib/util.smew", "def double(x):\n  return x * 2\nscale = 3\n"),
+      ("lib/shapes.smew", "box:\n  w: 5\n"),
+    ]);
+
+    let top = run_file(&mut Smew::new(), directory.join("main.smew")).unwrap();
+
+    assert_eq!(top.map["out"].clone().into_object(), Object::List(vec!(Object::Number(6.0), Object::Number(3.0), Object::Number(5.0))));
+
+    std::fs::remove_dir_all(directory).unwrap();
+  }
+
+  #[test]
+  fn imports_look_in_the_search_path_after_the_importing_file() {
+    let directory = files("search-path", &[
+      ("app/main.smew", "import \"util\"\nimport \"shared\"\nout:\n  [util.place, shared.place]\n"),
+      ("app/util.smew", "place: \"app\"\n"),
+      ("lib/util.smew", "place: \"lib\"\n"),
+      ("lib/shared.smew", "place: \"lib\"\n"),
+    ]);
+
+    let mut smew = Smew::new().with_search_path(directory.join("lib"));
+    let top      = run_file(&mut smew, directory.join("app/main.smew")).unwrap();
+
+    assert_eq!(top.map["out"].clone().into_object(), Object::List(vec!(Object::Str("app".into()), Object::Str("lib".into()))));
+
+    std::fs::remove_dir_all(directory).unwrap();
+  }
+
+  #[test]
+  fn import_errors() {
+    let directory = files("import-errors", &[
+      ("a.smew", "import \"b\"\n"),
+      ("b.smew", "import \"a\"\n"),
+      ("c.smew", "import nope from \"d\"\n"),
+      ("d.smew", "yes:\n  x: 1\n"),
+      ("e.smew", "import \"missing\"\n"),
+    ]);
+
+    let a = directory.join("a.smew").canonicalize().unwrap();
+    let b = directory.join("b.smew").canonicalize().unwrap();
+
+    assert_eq!(run_file(&mut Smew::new(), directory.join("a.smew")).unwrap_err(), format!("import cycle: {} -> {} -> {}", a.display(), b.display(), a.display()));
+    assert_eq!(run_file(&mut Smew::new(), directory.join("c.smew")).unwrap_err(), "no such thing as `nope` in `d`");
+    assert_eq!(run_file(&mut Smew::new(), directory.join("e.smew")).unwrap_err(), "can't find `missing` to import");
+
+    std::fs::remove_dir_all(directory).unwrap();
+  }
+
+  #[test]
+  fn own_fields_beat_inherited_ones() {
+    assert_eq!(value("a:\n  x: 1\nb -> a:\n  x: 2\nout:\n  b.x\n", "out"), Object::Number(2.0));
+  }
+
+  #[test]
+  fn parents_are_looked_at_left_to_right() {
+    assert_eq!(value("a:\n  x: 1\nb:\n  x: 2\nc -> a -> b:\n  y: 1\nout:\n  c.x\n", "out"), Object::Number(1.0));
+    assert_eq!(value("a:\n  x: 1\nb:\n  x: 2\nc -> b -> a:\n  y: 1\nout:\n  c.x\n", "out"), Object::Number(2.0));
+  }
+
+  #[test]
+  fn overrides_beat_a_shared_grandparent() {
+    let code = "g:\n  x: 1\n  y: 1\nl -> g:\n  z: 1\nr -> g:\n  x: 2\nd -> l -> r:\n  w: 1\nout:\n  [d.x, d.y]\n";
+
+    assert_eq!(value(code, "out"), Object::List(vec!(Object::Number(2.0), Object::Number(1.0))));
+  }
+
+  #[test]
+  fn parents_that_cannot_be_ordered() {
+    assert_eq!(error("g:\n  x: 1\nl -> g:\n  y: 1\nbad -> g -> l:\n  z: 1\n"), "can't order the parents so that each comes before the records it inherits from");
+  }
+
+  #[test]
+  fn long_lines_of_ancestors() {
+    let mut code = "r0:\n  x: 0\n".to_string();
+
+    for i in 1 ..= 40 {
+      code.push_str(&format!("r{} -> r{}:\n  y{}: {}\n", i, i - 1, i, i));
+    }
+
+    code.push_str("both -> r40 -> r39:\n  z: 1\nout:\n  [both.x, both.y40, r40 == r40]\n");
+
+    let top = run(&mut Smew::new(), &code).unwrap();
+
+    assert_eq!(top.map["out"].clone().into_object(), Object::List(vec!(Object::Number(0.0), Object::Number(40.0), Object::Bool(true))));
+    assert_eq!(top.map["both"].ancestors.len(), 41);
   }
-}
\ No newline at end of file
+}
@@ -1,9 +1,12 @@
+#[allow(clippy::module_inception)]
 pub mod interpreter;
 pub mod object;
+pub mod foreign;
 
 use self::super::parser::*;
-use self::super::lexer::Pos;
+use self::super::lexer::{ Lexer, Pos };
 use self::super::source::Source;
 
 pub use self::interpreter::*;
-pub use self::object::*;
\ No newline at end of file
+pub use self::object::*;
+pub use self::foreign::*;
\ No newline at end of file
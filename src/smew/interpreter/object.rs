@@ -1,12 +1,18 @@
-use std::collections::HashMap;
+use std::collections::{ HashMap, HashSet };
+use std::rc::Rc;
+use std::sync::atomic::{ AtomicUsize, Ordering };
 use std::fmt;
 
+use super::{ Statement, Captured };
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Object {
   Number(f64),
   Str(String),
   Bool(bool),
   Record(Record),
+  Function(Function),
+  List(Vec<Object>),
   Nil,
 }
 
@@ -19,7 +25,18 @@ impl Object {
       Bool(ref a)   => Str(a.to_string()),
       Str(ref a)    => Str(a.clone()),
       Nil           => Str(String::from("<nil>")),
-      _             => return None,
+
+      List(ref items) => {
+        let mut strings = Vec::new();
+
+        for item in items {
+          strings.push(item.to_str_object()?.to_string())
+        }
+
+        Str(format!("[{}]", strings.join(", ")))
+      },
+
+      _ => return None,
     };
 
     Some(result)
@@ -39,10 +56,22 @@ impl fmt::Display for Object {
 
 
 
-#[derive(Debug, Clone, PartialEq)]
+#[derive(Clone)]
 pub struct Record {
-  pub content: Vec<Object>,
-  pub map:     HashMap<String, Record>,
+  pub content:  Vec<Object>,
+  pub map:      HashMap<String, Record>,
+  pub value:    Option<Box<Object>>,
+
+  // a body that's left to run whenever it's asked for, like that of a `when-*` handler
+  pub deferred: Option<Deferred>,
+
+  // everything the record inherits from in the order fields are looked for, and the fields it got that way.
+  // ancestors are shared, so a long line of them is as cheap to copy as a short one
+  pub ancestors: Vec<Rc<Record>>,
+  pub inherited: HashSet<String>,
+
+  // which record this is, kept by all copies of it
+  pub id: usize,
 }
 
 impl Record {
@@ -50,6 +79,162 @@ impl Record {
     Record {
       content,
       map,
+      value:    None,
+      deferred: None,
+
+      ancestors: Vec::new(),
+      inherited: HashSet::new(),
+
+      id: NEXT_ID.fetch_add(1, Ordering::Relaxed),
     }
   }
-}
\ No newline at end of file
+
+  pub fn deferred(body: Rc<Vec<Statement>>) -> Self {
+    Record {
+      deferred: Some(Deferred::new(body)),
+
+      .. Record::new(Vec::new(), HashMap::new())
+    }
+  }
+
+  // whether the record has a field of its own rather than one it inherited
+  pub fn declares(&self, name: &str) -> bool {
+    self.map.contains_key(name) && !self.inherited.contains(name)
+  }
+
+  // what the record stands for when used as a value: whatever it returned,
+  // or the lone item of a body without fields, or else the record itself
+  pub fn into_object(self) -> Object {
+    if let Some(value) = self.value {
+      *value
+    } else if self.map.is_empty() && self.content.len() == 1 {
+      self.content.into_iter().next().unwrap()
+    } else {
+      Object::Record(self)
+    }
+  }
+
+  pub fn ancestor_ids(&self) -> Vec<usize> {
+    self.ancestors.iter().map(|ancestor| ancestor.id).collect()
+  }
+}
+
+static NEXT_ID: AtomicUsize = AtomicUsize::new(0);
+
+// records are equal when what's in them is, whichever records they are. ancestors only by which they are,
+// as comparing them whole would compare every ancestor once for every way it's inherited
+impl PartialEq for Record {
+  fn eq(&self, other: &Self) -> bool {
+    self.content == other.content
+      && self.map == other.map
+      && self.value == other.value
+      && self.deferred == other.deferred
+      && self.inherited == other.inherited
+      && self.ancestor_ids() == other.ancestor_ids()
+  }
+}
+
+impl fmt::Debug for Record {
+  fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    f.debug_struct("Record")
+      .field("content", &self.content)
+      .field("map", &self.map)
+      .field("value", &self.value)
+      .field("deferred", &self.deferred)
+      .field("ancestors", &self.ancestor_ids())
+      .field("inherited", &self.inherited)
+      .field("id", &self.id)
+      .finish()
+  }
+}
+
+
+
+// a record body kept for later, along with the bindings around where it was declared
+#[derive(Debug, Clone, PartialEq)]
+pub struct Deferred {
+  pub body:  Rc<Vec<Statement>>,
+  pub scope: Rc<HashMap<String, Object>>,
+}
+
+impl Deferred {
+  pub fn new(body: Rc<Vec<Statement>>) -> Self {
+    Deferred {
+      body,
+      scope: Rc::new(HashMap::new()),
+    }
+  }
+}
+
+
+
+// the c3 linearisation of some parents: every record comes before the ones it inherits from, and parents
+// keep the order they're listed in. nothing when no order can satisfy both
+pub fn linearize<T, F>(parents: &[T], ancestors: F) -> Option<Vec<T>>
+where
+  T: Clone + PartialEq,
+  F: Fn(&T) -> Vec<T>
+{
+  let mut unique: Vec<T> = Vec::new();
+
+  for parent in parents {
+    if !unique.contains(parent) {
+      unique.push(parent.clone())
+    }
+  }
+
+  let mut sequences = unique.iter().map(|parent| {
+    let mut sequence = vec!(parent.clone());
+
+    sequence.extend(ancestors(parent));
+    sequence
+  }).collect::<Vec<Vec<T>>>();
+
+  sequences.push(unique);
+
+  let mut order = Vec::new();
+
+  loop {
+    sequences.retain(|sequence| !sequence.is_empty());
+
+    if sequences.is_empty() {
+      return Some(order)
+    }
+
+    // the first head that no sequence still wants to see something else before
+    let next = sequences.iter()
+      .map(|sequence| &sequence[0])
+      .find(|head| sequences.iter().all(|sequence| !sequence[1 ..].contains(head)))?
+      .clone();
+
+    for sequence in sequences.iter_mut() {
+      if sequence[0] == next {
+        sequence.remove(0);
+      }
+    }
+
+    order.push(next)
+  }
+}
+
+
+
+// the frames a function was declared in are the ones its body sees, however it ends up being called
+#[derive(Debug, Clone, PartialEq)]
+pub struct Function {
+  pub name:   String,
+  pub params: Vec<String>,
+  pub body:   Rc<Vec<Statement>>,
+  pub scope:  Vec<Captured>,
+}
+
+impl Function {
+  pub fn new(name: String, params: Vec<String>, body: Rc<Vec<Statement>>, scope: Vec<Captured>) -> Self {
+    Function {
+      name,
+      params,
+      body,
+      scope,
+    }
+  }
+}
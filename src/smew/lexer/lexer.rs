@@ -1,13 +1,15 @@
 use super::*;
 
-use super::super::error::Response::Wrong;
+use super::super::error::SmewError;
 
 use std::rc::Rc;
 
 pub struct Lexer<'l> {
   tokenizer: Tokenizer<'l>,
-  matchers:  Vec<Rc<Matcher<'l>>>,
+  matchers:  Vec<Rc<dyn Matcher<'l>>>,
   source:    &'l Source,
+
+  errors: Vec<SmewError>,
 }
 
 impl<'l> Lexer<'l> {
@@ -16,6 +18,8 @@ impl<'l> Lexer<'l> {
       tokenizer,
       matchers: Vec::new(),
       source,
+
+      errors: Vec::new(),
     }
   }
 
@@ -33,7 +37,8 @@ impl<'l> Lexer<'l> {
     lexer.matchers.push(
       Rc::new(
         KeyMatcher::new(Keyword, &[
-          "def", "import", "->",
+          "def", "import", "return", "if", "elif", "else",
+          "for", "in", "while", "break", "continue", "->",
         ])
       )
     );
@@ -49,7 +54,7 @@ impl<'l> Lexer<'l> {
 
     lexer.matchers.push(
       Rc::new(
-        ConstantStringMatcher::new(Operator, &[".", "^", "++", "+", "-", "*", "/", "%", "==", "!=", "<=", ">=", "<", ">", "or", "and", "not"])
+        ConstantStringMatcher::new(Operator, &[".", "^", "++", "+", "-", "*", "//", "/", "%", "==", "!=", "<=", ">=", "<", ">", "or", "and", "not"])
       )
     );
 
@@ -57,14 +62,38 @@ impl<'l> Lexer<'l> {
 
     lexer.matchers.push(
       Rc::new(
-        ConstantCharMatcher::new(Symbol, &['(', ')', '[', ']', '{', '}', ',', ':', ';', '=', '.', '|'])
+        ConstantCharMatcher::new(Symbol, &['(', ')', '[', ']', '{', '}', ',', ':', ';', '=', '.', '|', '!'])
       )
     );
 
     lexer
   }
 
-  pub fn match_token(&mut self) -> Result<Option<Token>, ()> {
+  // every token, with whatever couldn't be lexed turned into `TokenType::Error`, and what went wrong there
+  pub fn tokenize(mut self) -> (Vec<Token>, Vec<SmewError>) {
+    let tokens = self.by_ref().collect();
+
+    (tokens, self.errors)
+  }
+
+  // skips to a point where lexing can carry on, covering what was skipped with an error token
+  fn skip(&mut self, skip: fn(&mut Tokenizer<'l>)) -> Token {
+    let (line, start) = self.tokenizer.pos;
+    let index         = self.tokenizer.index;
+
+    skip(&mut self.tokenizer);
+
+    let lexeme = self.tokenizer.items[index .. self.tokenizer.index.min(self.tokenizer.items.len())].iter().collect::<String>();
+
+    Token::new(
+      TokenType::Error,
+      (line, self.source.line(line)),
+      (start + 1, start + lexeme.chars().count()),
+      &lexeme
+    )
+  }
+
+  pub fn match_token(&mut self) -> Result<Option<Token>, SmewError> {
     for matcher in &mut self.matchers {
       match self.tokenizer.try_match_token(matcher.as_ref())? {
         Some(t) => return Ok(Some(t)),
@@ -77,37 +106,74 @@ impl<'l> Lexer<'l> {
 }
 
 impl<'l> Iterator for Lexer<'l> {
-  type Item = Result<Token, ()>;
+  type Item = Token;
 
-  fn next(&mut self) -> Option<Result<Token, ()>> {
+  fn next(&mut self) -> Option<Token> {
     let token = match self.match_token() {
-      Ok(hmm) => match hmm {
-        Some(n) => n,
-        None    => {
-          let pos = self.tokenizer.pos;
-
-          return Some(
-            Err(
-              response!(
-                Wrong("bumped into weird character"),
-                self.source.file,
-                Pos(
-                  (pos.0, self.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(self.source.lines.last().unwrap_or(&String::new())).to_string()),
-                  (pos.1 + 1, pos.1 + 1),
-                )
-              )
+      Ok(Some(token)) => token,
+
+      Ok(None) => {
+        let pos = self.tokenizer.pos;
+
+        self.errors.push(
+          self.tokenizer.error(
+            "bumped into weird character",
+            Pos(
+              (pos.0, self.source.line(pos.0)),
+              (pos.1 + 1, pos.1 + 1),
             )
           )
-        },
+        );
+
+        self.skip(|tokenizer| tokenizer.advance())
       },
 
-      Err(_) => return Some(Err(())),
+      // the matcher left off somewhere inside the broken token, so start over from its beginning
+      Err(error) => {
+        self.errors.push(error);
+        self.tokenizer.rollback_snapshot();
+
+        self.skip(|tokenizer| {
+          while !tokenizer.end() && tokenizer.peek() != Some('\n') {
+            tokenizer.advance()
+          }
+        })
+      },
     };
 
     match token.token_type {
       TokenType::EOF        => None,
       TokenType::Whitespace => self.next(),
-      _                     => Some(Ok(token)),
+      _                     => Some(token),
     }
   }
-}
\ No newline at end of file
+}
+
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn lexing_carries_on_past_errors() {
+    let code   = "a = 1 @ 2\nb = \"abc\\q def\"\nc = 1.2.3 + 4\nd = 5\nf = \"unterminated\nprint(d)\n";
+    let source = Source::from("<test>", code.lines().map(|x| x.to_string()).collect());
+
+    let (tokens, errors) = Lexer::default(source.chars(), &source).tokenize();
+
+    let found = errors.iter().map(|error| (error.message.as_str(), (error.pos.as_ref().unwrap().0).0)).collect::<Vec<(&str, usize)>>();
+
+    assert_eq!(found, vec!(
+      ("bumped into weird character", 1),
+      ("unexpected escape character: q", 2),
+      ("unexpected extra decimal point", 3),
+      ("unterminated delimeter `\"`", 5),
+    ));
+
+    // what's broken becomes error tokens, and everything around it is still there
+    assert_eq!(tokens.iter().filter(|token| token.token_type == TokenType::Error).count(), 4);
+    assert!(tokens.iter().any(|token| token.lexeme == "print"));
+    assert!(tokens.iter().any(|token| token.token_type == TokenType::Number && token.lexeme == "5"));
+  }
+}
@@ -1,5 +1,7 @@
 use super::*;
-use super::super::error::Response::*;
+use super::super::error::SmewError;
+
+use std::mem;
 
 
 macro_rules! token {
@@ -13,9 +15,9 @@ macro_rules! token {
     let accum: String = $accum;
     let pos           = tokenizer.last_position();
 
-    let line = tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap()).to_string();
+    let line = tokenizer.source.line(pos.0);
 
-    if TokenType::Str == token_type {
+    if TokenType::Str == token_type || TokenType::Template == token_type {
       Token::new(token_type, (pos.0, line), (pos.1 + 1, pos.1 + accum.len() + 2), &accum) // delimeters
     } else {
       Token::new(token_type, (pos.0, line), (pos.1 + 1, pos.1 + accum.len()), &accum)
@@ -26,7 +28,7 @@ macro_rules! token {
 
 
 pub trait Matcher<'t> {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()>;
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError>;
 }
 
 
@@ -34,8 +36,8 @@ pub trait Matcher<'t> {
 pub struct CommentMatcher;
 
 impl<'t> Matcher<'t> for CommentMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
-    if tokenizer.peek_range(1).unwrap_or_else(String::new) == "#" {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
+    if tokenizer.peek_range(1).unwrap_or_default() == "#" {
       while !tokenizer.end() && tokenizer.peek() != Some('\n') {
         tokenizer.advance()
       }
@@ -64,7 +66,7 @@ impl ConstantStringMatcher {
 }
 
 impl<'t> Matcher<'t> for ConstantStringMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
     for constant in self.constants {
       let len = constant.len();
       let c   = match tokenizer.peek_range(len) {
@@ -107,7 +109,7 @@ impl ConstantCharMatcher {
 }
 
 impl<'t> Matcher<'t> for ConstantCharMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
     let c = tokenizer.peek().unwrap();
     
     for constant in self.constants {
@@ -133,7 +135,7 @@ impl<'t> Matcher<'t> for ConstantCharMatcher {
 pub struct StringLiteralMatcher;
 
 impl<'t> Matcher<'t> for StringLiteralMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
     let mut raw_marker = false;
 
     let mut pos = tokenizer.pos;
@@ -151,11 +153,10 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
           '"'
         } else if tokenizer.peek_n(1) == Some('\'') {
           return Err(
-            response!(
-              Wrong("no such thing as a raw character literal"),
-              tokenizer.source.file,
+            tokenizer.error(
+              "no such thing as a raw character literal",
               Pos(
-                (pos.0, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap()).to_string()),
+                (pos.0, tokenizer.source.line(pos.0)),
                 (pos.1 - 1, pos.1),
               )
             )
@@ -172,15 +173,18 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
     let mut string       = String::new();
     let mut found_escape = false;
 
+    // interpolated strings are handed on as written, for the parser to pick apart
+    let mut raw          = String::new();
+    let mut interpolated = false;
+
     loop {
       if tokenizer.end() {
         return Err(
-          response!(
-            Wrong(format!("unterminated delimeter `{}`", delimeter)),
-            tokenizer.source.file,
+          tokenizer.error(
+            format!("unterminated delimeter `{}`", delimeter),
             Pos(
-              (pos.0 + 1, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap()).to_string()),
-              (pos.1.saturating_sub(1), pos.1 + 1),
+              (pos.0, tokenizer.source.line(pos.0)),
+              (pos.1 + 1, pos.1 + 1),
             )
           )
         )
@@ -193,18 +197,18 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 
         string.push(tokenizer.next().unwrap())
       } else if found_escape {
+        let escaped = tokenizer.next().unwrap();
+
+        raw.push(escaped);
+
         string.push(
-          match tokenizer.next().unwrap() {
-            c @ '\\' | c @ '\'' | c @ '"' => c,
-            'n' => '\n',
-            'r' => '\r',
-            't' => '\t',
-            escaped => return Err(
-              response!(
-                Wrong(format!("unexpected escape character: {}", escaped)),
-                tokenizer.source.file,
+          match unescape(escaped) {
+            Some(c) => c,
+            None    => return Err(
+              tokenizer.error(
+                format!("unexpected escape character: {}", escaped),
                 Pos(
-                  (tokenizer.pos.0, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap()).to_string()),
+                  (tokenizer.pos.0, tokenizer.source.line(pos.0)),
                   (tokenizer.pos.1 - 1, tokenizer.pos.1),
                 )
               )
@@ -216,19 +220,38 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
       } else {
         match tokenizer.peek().unwrap() {
           '\\' => {
-            tokenizer.next();
+            raw.push(tokenizer.next().unwrap());
             found_escape = true
           },
 
-          // check for valid closing delimeter and alternative
-          c => if c == delimeter {
-            if string.len() > 0 && string != " " {
-              break
-            } else {
-              string.push(tokenizer.next().unwrap())
+          '{' => {
+            let len = match embedded_len(|n| tokenizer.peek_n(n)) {
+              Some(len) => len,
+              None      => return Err(
+                tokenizer.error(
+                  "unclosed `{` in string",
+                  Pos(
+                    (tokenizer.pos.0, tokenizer.source.line(tokenizer.pos.0)),
+                    (tokenizer.pos.1 + 1, tokenizer.pos.1 + 1),
+                  )
+                )
+              ),
+            };
+
+            for _ in 0 .. len {
+              raw.push(tokenizer.next().unwrap())
             }
-          } else {
-            string.push(tokenizer.next().unwrap())
+
+            interpolated = true
+          },
+
+          c if c == delimeter => break,
+
+          _ => {
+            let c = tokenizer.next().unwrap();
+
+            raw.push(c);
+            string.push(c)
           },
         }
       }
@@ -236,8 +259,107 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 
     tokenizer.advance();
 
-    Ok(Some(token!(tokenizer, Str, string)))
+    if interpolated {
+      Ok(Some(token!(tokenizer, Template, raw)))
+    } else {
+      Ok(Some(token!(tokenizer, Str, string)))
+    }
+  }
+}
+
+
+
+// what an escaped character in a string stands for
+pub fn unescape(c: char) -> Option<char> {
+  match c {
+    '\\' | '\'' | '"' | '{' | '}' => Some(c),
+    'n' => Some('\n'),
+    'r' => Some('\r'),
+    't' => Some('\t'),
+    _   => None,
+  }
+}
+
+// the length of an embedded `{expression}` starting at `peek(0)`, strings inside it included
+pub fn embedded_len<F: Fn(usize) -> Option<char>>(peek: F) -> Option<usize> {
+  let mut depth   = 0;
+  let mut quote   = None;
+  let mut escaped = false;
+
+  for len in 1 .. {
+    let c = match peek(len - 1) {
+      Some('\n') | None => return None,
+      Some(c)           => c,
+    };
+
+    match quote {
+      Some(_) if escaped       => escaped = false,
+      Some(_) if c == '\\'    => escaped = true,
+      Some(q) if c == q        => quote = None,
+      Some(_)                  => (),
+
+      None => match c {
+        '{'        => depth += 1,
+        '}'        => {
+          depth -= 1;
+
+          if depth == 0 {
+            return Some(len)
+          }
+        },
+        '"' | '\'' => quote = Some(c),
+        _          => (),
+      },
+    }
   }
+
+  None
+}
+
+pub enum Piece {
+  Text(String),
+  Code(usize, String), // an embedded expression, and where it starts in the string
+}
+
+// splits the contents of an interpolated string, which the lexer already made sure is well-formed
+pub fn pieces(raw: &str) -> Vec<Piece> {
+  let chars = raw.chars().collect::<Vec<char>>();
+
+  let mut pieces = Vec::new();
+  let mut text   = String::new();
+  let mut index  = 0;
+
+  while index < chars.len() {
+    match chars[index] {
+      '\\' if index + 1 < chars.len() => {
+        text.push(unescape(chars[index + 1]).unwrap_or(chars[index + 1]));
+        index += 2
+      },
+
+      '{' => {
+        let len = embedded_len(|n| chars.get(index + n).cloned()).unwrap_or(chars.len() - index);
+
+        if !text.is_empty() {
+          pieces.push(Piece::Text(mem::take(&mut text)))
+        }
+
+        pieces.push(Piece::Code(index + 1, chars[index + 1 .. index + len - 1].iter().collect()));
+
+        index += len
+      },
+
+      c => {
+        text.push(c);
+        index += 1
+      },
+    }
+  }
+
+  if !text.is_empty() {
+    pieces.push(Piece::Text(text))
+  }
+
+  pieces
 }
 
 
@@ -245,12 +367,12 @@ impl<'t> Matcher<'t> for StringLiteralMatcher {
 pub struct IdentifierMatcher;
 
 impl<'t> Matcher<'t> for IdentifierMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
     if !tokenizer.peek().unwrap().is_alphabetic() {
       return Ok(None)
     }
 
-    let accum = tokenizer.collect_while(|c| c.is_alphanumeric() || "_-!?".contains(c));
+    let accum = tokenizer.collect_while(|c| c.is_alphanumeric() || "_-?".contains(c));
 
     if accum.is_empty() {
       Ok(None)
@@ -264,11 +386,11 @@ impl<'t> Matcher<'t> for IdentifierMatcher {
 pub struct NumberLiteralMatcher;
 
 impl<'t> Matcher<'t> for NumberLiteralMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
     let mut accum = String::new();
 
     let curr = tokenizer.next().unwrap();
-    if curr.is_digit(10) {
+    if curr.is_ascii_digit() {
       accum.push(curr)
     } else if curr == '.' {
       accum.push_str("0.")
@@ -280,16 +402,15 @@ impl<'t> Matcher<'t> for NumberLiteralMatcher {
 
     while !tokenizer.end() {
       let current = tokenizer.peek().unwrap();
-      if !current.is_whitespace() && current.is_digit(10) || current == '.' {
+      if !current.is_whitespace() && current.is_ascii_digit() || current == '.' {
         if current == '.' && accum.contains('.') {
           let pos = tokenizer.pos;
           
           return Err(
-            response!(
-              Wrong("unexpected extra decimal point"),
-              tokenizer.source.file,
+            tokenizer.error(
+              "unexpected extra decimal point",
               Pos(
-                (pos.0, tokenizer.source.lines.get(pos.0.saturating_sub(1)).unwrap_or(tokenizer.source.lines.last().unwrap()).to_string()),
+                (pos.0, tokenizer.source.line(pos.0)),
                 (pos.1 + 1, pos.1 + 1),
               )
             )
@@ -329,12 +450,12 @@ impl KeyMatcher {
 }
 
 impl<'t> Matcher<'t> for KeyMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
     for constant in self.constants {
       if let Some(s) = tokenizer.peek_range(constant.len()) {
         if s == *constant {
           if let Some(c) = tokenizer.peek_n(constant.len()) {
-            if "_!?".contains(c) || c.is_alphanumeric() {
+            if "_-?".contains(c) || c.is_alphanumeric() {
                 return Ok(None)
             }
           }
@@ -354,7 +475,7 @@ impl<'t> Matcher<'t> for KeyMatcher {
 pub struct EOLMatcher;
 
 impl<'t> Matcher<'t> for EOLMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
     if tokenizer.peek() == Some('\n') {
       tokenizer.pos.0 += 1;
       tokenizer.pos.1 = 0;
@@ -372,7 +493,7 @@ impl<'t> Matcher<'t> for EOLMatcher {
 pub struct WhitespaceMatcher;
 
 impl<'t> Matcher<'t> for WhitespaceMatcher {
-  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, ()> {
+  fn try_match(&self, tokenizer: &mut Tokenizer<'t>) -> Result<Option<Token>, SmewError> {
     let string = tokenizer.collect_while(|c| c.is_whitespace() && c != '\n');
 
     if !string.is_empty() {
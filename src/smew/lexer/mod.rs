@@ -1,6 +1,7 @@
 pub mod token;
 pub mod matcher;
 pub mod tokenizer;
+#[allow(clippy::module_inception)]
 pub mod lexer;
 
 use super::source::Source;
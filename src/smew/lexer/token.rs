@@ -7,12 +7,14 @@ pub enum TokenType {
   Number,
   Keyword,
   Str,
+  Template, // a string with `{expression}`s in it
   Symbol,
   Operator,
   Bool,
   Whitespace,
   EOL,
   EOF,
+  Error, // what was skipped over after a lexical error
 }
 
 impl fmt::Display for TokenType {
@@ -23,6 +25,7 @@ impl fmt::Display for TokenType {
       Identifier => write!(f, "Name"),
       Number     => write!(f, "Number"),
       Str        => write!(f, "Text"),
+      Template   => write!(f, "Text"),
       Keyword    => write!(f, "Keyword"),
       Bool       => write!(f, "Bool"),
       Symbol     => write!(f, "Symbol"),
@@ -30,6 +33,7 @@ impl fmt::Display for TokenType {
       Whitespace => write!(f, "Whitespace"),
       EOL        => write!(f, "EOL"),
       EOF        => write!(f, "EOF"),
+      Error      => write!(f, "Error"),
     }
   }
 }
@@ -47,7 +51,16 @@ impl fmt::Display for Pos {
   fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
     let linepad = format!("{:5} │", " ").blue().bold();
     let lineno = format!("{:5} │ ", (self.0).0).blue().bold();
-    let mut mark = (self.0).1[(self.1).0.saturating_sub(1) .. (self.1).1].to_string();
+
+    // positions at the end of a line, like those of new lines, may reach past it
+    let chars = (self.0).1.chars().collect::<Vec<char>>();
+    let start = (self.1).0.saturating_sub(1).min(chars.len());
+    let end   = (self.1).1.max(start).min(chars.len());
+
+    let before = chars[.. start].iter().collect::<String>();
+    let after  = chars[end ..].iter().collect::<String>();
+
+    let mut mark = chars[start .. end].iter().collect::<String>();
 
     if mark.split_whitespace().count() == 0 {
       mark = format!("{:─>count$}", ">".red().bold(), count=mark.len());
@@ -57,13 +70,13 @@ impl fmt::Display for Pos {
 
     let mut arrows = format!("{: <count$}", " ", count=(self.1).0);
 
-    for _ in 0 .. (self.1).1 - (self.1).0 + 1 {
+    for _ in 0 .. (self.1).1.saturating_sub((self.1).0) + 1 {
       arrows.push('^')
     }
 
     write!(f, "\n{}\n{}{}{}{}\n{}{}",
       linepad,
-      lineno, &(self.0).1[..(self.1).0.saturating_sub(1)], mark, &(self.0).1[(self.1).1..],
+      lineno, before, mark, after,
       linepad,
       arrows.red().bold()
     )
@@ -1,5 +1,6 @@
 use super::token::*;
 use super::{ Source, Matcher, };
+use super::super::error::{ SmewError, ErrorKind };
 
 pub struct Snapshot {
   pub index: usize,
@@ -43,7 +44,7 @@ impl<'t> Tokenizer<'t> {
   }
 
   pub fn advance(&mut self) {
-    if let Some(item) = self.items.get(self.index + 1) {
+    if self.items.get(self.index + 1).is_some() {
       self.pos.1 += 1
     }
 
@@ -90,13 +91,13 @@ impl<'t> Tokenizer<'t> {
     self.peek_snapshot().unwrap_or(&Snapshot::new(0, (0, 0))).pos
   }
 
-  pub fn try_match_token(&mut self, matcher: &Matcher<'t>) -> Result<Option<Token>, ()> {
+  pub fn try_match_token(&mut self, matcher: &dyn Matcher<'t>) -> Result<Option<Token>, SmewError> {
     if self.end() {
       return Ok(
         Some(
           Token::new(
             TokenType::EOF,
-            (self.pos.0, if self.source.lines.len() > 0 {
+            (self.pos.0, if !self.source.lines.is_empty() {
                 self.source.lines.get(self.pos.0).unwrap_or(self.source.lines.first().unwrap()).to_string()
               } else {
                 String::new()
@@ -124,6 +125,10 @@ impl<'t> Tokenizer<'t> {
     }
   }
 
+  pub fn error<T: Into<String>>(&self, message: T, pos: Pos) -> SmewError {
+    SmewError::at(ErrorKind::Lexer, message, &self.source.file, pos)
+  }
+
   pub fn collect_while(&mut self, func: fn(char) -> bool) -> String {
     let mut accum = String::new();
     while let Some(c) = self.peek() {
@@ -0,0 +1,519 @@
+use std::collections::{ HashMap, HashSet };
+
+use super::super::error::{ SmewError, ErrorKind, Severity };
+use super::super::interpreter::linearize;
+use super::*;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+  UnusedAssignment,
+  RedefinedField,
+  DuplicateParent,
+  DiscardedValue,
+  ConflictingField,
+}
+
+impl Lint {
+  pub const ALL: [Lint; 5] = [
+    Lint::UnusedAssignment,
+    Lint::RedefinedField,
+    Lint::DuplicateParent,
+    Lint::DiscardedValue,
+    Lint::ConflictingField,
+  ];
+
+  pub fn name(&self) -> &'static str {
+    use self::Lint::*;
+
+    match *self {
+      UnusedAssignment => "unused-assignment",
+      RedefinedField   => "redefined-field",
+      DuplicateParent  => "duplicate-parent",
+      DiscardedValue   => "discarded-value",
+      ConflictingField => "conflicting-field",
+    }
+  }
+
+  pub fn from_name(name: &str) -> Option<Self> {
+    Lint::ALL.iter().find(|lint| lint.name() == name).cloned()
+  }
+}
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+  Allow,
+  Warn,
+  Deny,
+}
+
+
+
+// how loudly each lint speaks up, everything warns unless told otherwise
+#[derive(Debug, Clone, Default)]
+pub struct Lints {
+  levels: HashMap<Lint, Level>,
+}
+
+impl Lints {
+  pub fn new() -> Self {
+    Lints::default()
+  }
+
+  pub fn set(&mut self, lint: Lint, level: Level) {
+    self.levels.insert(lint, level);
+  }
+
+  pub fn level(&self, lint: Lint) -> Level {
+    self.levels.get(&lint).cloned().unwrap_or(Level::Warn)
+  }
+
+  // warnings, and errors for denied lints, in the order they were found
+  pub fn check(&self, source: &Source, ast: &[Statement]) -> Vec<SmewError> {
+    let mut linter = Linter {
+      lints:   self,
+      source,
+      found:   Vec::new(),
+      path:    Vec::new(),
+      records: HashMap::new(),
+    };
+
+    linter.statements(ast, Scope::File);
+
+    linter.found
+  }
+}
+
+
+
+// the bodies that get a frame of their own when evaluated
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Scope {
+  File,
+  Record,
+  Function,
+}
+
+struct Linter<'l> {
+  lints:  &'l Lints,
+  source: &'l Source,
+  found:  Vec<SmewError>,
+
+  // the records around the one being looked at, and every record seen so far by its full path
+  path:    Vec<String>,
+  records: HashMap<String, Known>,
+}
+
+// the fields a record declares itself, and the records it inherits from in the order they're looked in
+struct Known {
+  own:       HashSet<String>,
+  ancestors: Vec<String>,
+}
+
+impl<'l> Linter<'l> {
+  fn report<T: Into<String>>(&mut self, lint: Lint, message: T, pos: &Pos) -> Option<&mut SmewError> {
+    let severity = match self.lints.level(lint) {
+      Level::Allow => return None,
+      Level::Warn  => Severity::Warning,
+      Level::Deny  => Severity::Error,
+    };
+
+    let warning = SmewError::at(ErrorKind::Lint, message, &self.source.file, pos.clone())
+      .with_severity(severity)
+      .with_hint(format!("from the `{}` lint", lint.name()));
+
+    self.found.push(warning);
+    self.found.last_mut()
+  }
+
+
+
+  // remembers what a record declares and inherits from, and reports fields it gets from two
+  // unrelated records without settling which one it wants
+  fn inherit(&mut self, name: &String, parents: &[Expression], body: &[Statement]) {
+    let full = self.path.iter().chain(Some(name)).cloned().collect::<Vec<_>>().join(".");
+
+    let mut own = Vec::new();
+
+    fields(body, &mut own);
+
+    let own = own.into_iter().cloned().collect::<HashSet<String>>();
+
+    let resolved = parents.iter()
+      .filter_map(|parent| Some((self.resolve(&path(parent)?)?, &parent.pos)))
+      .collect::<Vec<(String, &Pos)>>();
+
+    let names     = resolved.iter().map(|(name, _)| name.clone()).collect::<Vec<String>>();
+    let ancestors = linearize(&names, |parent| self.records[parent].ancestors.clone()).unwrap_or_default();
+
+    let mut inherited = ancestors.iter()
+      .flat_map(|ancestor| self.records[ancestor].own.iter().cloned())
+      .filter(|field| !own.contains(field))
+      .collect::<Vec<String>>();
+
+    inherited.sort();
+    inherited.dedup();
+
+    for field in inherited {
+      let owners = ancestors.iter().filter(|ancestor| self.records[*ancestor].own.contains(&field)).collect::<Vec<_>>();
+
+      for (index, owner) in owners.iter().enumerate().skip(1) {
+        // an override beats whatever it overrides, wherever that turns up
+        if owners[.. index].iter().any(|earlier| self.records[*earlier].ancestors.contains(owner)) {
+          continue
+        }
+
+        let first = owners[0];
+
+        // pointed at the parent it came through
+        let pos = resolved.iter()
+          .find(|(parent, _)| parent == *owner || self.records[parent].ancestors.contains(owner))
+          .map(|&(_, pos)| pos.clone())
+          .unwrap();
+
+        let message = format!("`{}` and `{}` both have a `{}` field", first, owner, field);
+
+        if let Some(warning) = self.report(Lint::ConflictingField, message, &pos) {
+          warning.hints.insert(0, format!("the one from `{}` is used, since it comes first", first))
+        }
+      }
+    }
+
+    self.records.insert(full, Known { own, ancestors });
+  }
+
+  // the full path of a record named from inside the current one, looking outwards like names do
+  fn resolve(&self, path: &str) -> Option<String> {
+    (0 ..= self.path.len()).rev().find_map(|depth| {
+      let full = self.path[.. depth].iter().map(String::as_str).chain(Some(path)).collect::<Vec<_>>().join(".");
+
+      if self.records.contains_key(&full) { Some(full) } else { None }
+    })
+  }
+
+
+
+  // a body with a frame of its own, where assignments can be checked for use
+  fn scope(&mut self, body: &[Statement], scope: Scope) {
+    let mut assigned = Vec::new();
+    let mut read     = HashSet::new();
+
+    assignments(body, &mut assigned);
+
+    for statement in body.iter() {
+      reads(statement, &mut read)
+    }
+
+    let mut reported = HashSet::new();
+
+    for (name, pos) in assigned {
+      if read.contains(name) || !reported.insert(name) {
+        continue
+      }
+
+      self.report(
+        Lint::UnusedAssignment,
+        format!("`{}` is assigned but never used", name),
+        pos
+      );
+    }
+
+    self.statements(body, scope)
+  }
+
+  fn statements(&mut self, body: &[Statement], scope: Scope) {
+    use self::StatementNode::*;
+
+    let mut fields: HashMap<&String, usize> = HashMap::new();
+
+    // a `return` replaces whatever the body evaluated to up until then
+    let returns = body.iter().rposition(|statement| matches!(statement.node, Return(_)));
+
+    for (index, statement) in body.iter().enumerate() {
+      match statement.node {
+        Expression(ref expression) => {
+          let last      = index + 1 == body.len();
+          let discarded = returns.is_some_and(|returns| index < returns) || scope == Scope::Function && !last;
+
+          if discarded && !expression.calls() {
+            self.report(
+              Lint::DiscardedValue,
+              "this value is never used",
+              &statement.pos
+            );
+          }
+        },
+
+        Record(ref name, ref parents, ref inner) => {
+          let line = (statement.pos.0).0;
+
+          if let Some(first) = fields.insert(name, line) {
+            if let Some(warning) = self.report(Lint::RedefinedField, format!("field `{}` is defined twice", name), &statement.pos) {
+              warning.hints.insert(0, format!("the first one is on line {}", first))
+            }
+
+            fields.insert(name, first);
+          }
+
+          let mut inherited = HashSet::new();
+
+          for parent in parents {
+            if let Some(path) = path(parent) {
+              if !inherited.insert(path.clone()) {
+                self.report(
+                  Lint::DuplicateParent,
+                  format!("`{}` is inherited twice", path),
+                  &parent.pos
+                );
+              }
+            }
+          }
+
+          self.inherit(name, parents, inner);
+
+          self.path.push(name.clone());
+          self.scope(inner, Scope::Record);
+          self.path.pop();
+        },
+
+        Function(_, _, ref inner) => self.scope(inner, Scope::Function),
+
+        If(ref branches, ref otherwise) => {
+          for (_, inner) in branches.iter() {
+            self.statements(inner, scope)
+          }
+
+          if let Some(ref inner) = *otherwise {
+            self.statements(inner, scope)
+          }
+        },
+
+        For(_, _, ref inner) | While(_, ref inner) => self.statements(inner, scope),
+
+        _ => (),
+      }
+    }
+  }
+}
+
+
+
+// assignments made in a scope, looking into branches and loops since those share its frame
+fn assignments<'a>(body: &'a [Statement], assigned: &mut Vec<(&'a String, &'a Pos)>) {
+  use self::StatementNode::*;
+
+  for statement in body.iter() {
+    match statement.node {
+      Assignment(ref name, _) => assigned.push((name, &statement.pos)),
+
+      If(ref branches, ref otherwise) => {
+        for (_, inner) in branches.iter() {
+          assignments(inner, assigned)
+        }
+
+        if let Some(ref inner) = *otherwise {
+          assignments(inner, assigned)
+        }
+      },
+
+      For(_, _, ref inner) | While(_, ref inner) => assignments(inner, assigned),
+
+      _ => (),
+    }
+  }
+}
+
+// the fields a body declares, including the ones in its branches and loops
+fn fields<'a>(body: &'a [Statement], found: &mut Vec<&'a String>) {
+  use self::StatementNode::*;
+
+  for statement in body.iter() {
+    match statement.node {
+      Record(ref name, _, _) => found.push(name),
+
+      If(ref branches, ref otherwise) => {
+        for (_, inner) in branches.iter() {
+          fields(inner, found)
+        }
+
+        if let Some(ref inner) = *otherwise {
+          fields(inner, found)
+        }
+      },
+
+      For(_, _, ref inner) | While(_, ref inner) => fields(inner, found),
+
+      _ => (),
+    }
+  }
+}
+
+// every name read in a statement, nested bodies included
+fn reads(statement: &Statement, read: &mut HashSet<String>) {
+  use self::StatementNode::*;
+
+  let mut read_expression = |expression: &super::Expression| expression.visit(&mut |expression| {
+    if let ExpressionNode::Identifier(ref name) = expression.node {
+      read.insert(name.clone());
+    }
+  });
+
+  let mut inner: Vec<&Vec<Statement>> = Vec::new();
+
+  match statement.node {
+    Expression(ref expression) | Assignment(_, ref expression) => read_expression(expression),
+
+    Return(Some(ref expression)) => read_expression(expression),
+
+    Record(_, ref parents, ref body) => {
+      parents.iter().for_each(&mut read_expression);
+      inner.push(body)
+    },
+
+    Function(_, _, ref body) => inner.push(body),
+
+    If(ref branches, ref otherwise) => {
+      for (condition, body) in branches.iter() {
+        read_expression(condition);
+        inner.push(body)
+      }
+
+      inner.extend(otherwise.iter())
+    },
+
+    For(_, ref iterable, ref body) => {
+      read_expression(iterable);
+      inner.push(body)
+    },
+
+    While(ref condition, ref body) => {
+      read_expression(condition);
+      inner.push(body)
+    },
+
+    _ => (),
+  }
+
+  for body in inner {
+    for statement in body.iter() {
+      reads(statement, read)
+    }
+  }
+}
+
+// `a.b.c` for parents written as a plain path
+fn path(expression: &Expression) -> Option<String> {
+  match expression.node {
+    ExpressionNode::Identifier(ref name)   => Some(name.clone()),
+    ExpressionNode::Field(ref a, ref name) => Some(format!("{}.{}", path(a)?, name)),
+    _                                      => None,
+  }
+}
+
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn lint_with(lints: &Lints, code: &str) -> Vec<SmewError> {
+    let source = Source::from("<test>", code.lines().map(String::from).collect());
+    let ast    = crate::parse(&source).unwrap();
+
+    lints.check(&source, &ast)
+  }
+
+  fn lint(code: &str) -> Vec<SmewError> {
+    lint_with(&Lints::new(), code)
+  }
+
+  fn messages(found: &[SmewError]) -> Vec<&str> {
+    found.iter().map(|x| x.message.as_str()).collect()
+  }
+
+  #[test]
+  fn unused_assignment() {
+    let found = lint("box:\n  a = 1\n  b = 2\n  size: b\n");
+
+    assert_eq!(messages(&found), vec!("`a` is assigned but never used"));
+    assert_eq!(found[0].hints, vec!("from the `unused-assignment` lint"));
+  }
+
+  #[test]
+  fn assignments_read_in_a_loop_are_used() {
+    assert!(lint("def count():\n  n = 0\n  for i in 3:\n    n = n + i\n  return n\n").is_empty());
+  }
+
+  #[test]
+  fn redefined_field() {
+    let found = lint("box:\n  size: 1\n  size: 2\n");
+
+    assert_eq!(messages(&found), vec!("field `size` is defined twice"));
+    assert_eq!(found[0].hints[0], "the first one is on line 2");
+  }
+
+  #[test]
+  fn duplicate_parent() {
+    let found = lint("a:\n  x: 1\nb -> a -> a:\n  y: 1\n");
+
+    assert_eq!(messages(&found), vec!("`a` is inherited twice"));
+  }
+
+  #[test]
+  fn discarded_value() {
+    assert_eq!(messages(&lint("def f():\n  1 + 1\n  return 2\n")), vec!("this value is never used"));
+  }
+
+  #[test]
+  fn calls_are_not_discarded_values() {
+    assert!(lint("def f():\n  g()\n  return 2\n").is_empty());
+  }
+
+  #[test]
+  fn conflicting_field() {
+    let found = lint("a:\n  x: 1\nb:\n  x: 2\nc -> a -> b:\n  y: 1\n");
+
+    assert_eq!(messages(&found), vec!("`a` and `b` both have a `x` field"));
+    assert_eq!(found[0].hints[0], "the one from `a` is used, since it comes first");
+  }
+
+  #[test]
+  fn settled_fields_do_not_conflict() {
+    assert!(lint("a:\n  x: 1\nb:\n  x: 2\nc -> a -> b:\n  x: 3\n").is_empty());
+  }
+
+  #[test]
+  fn overrides_in_a_diamond_do_not_conflict() {
+    assert!(lint("g:\n  x: 1\nl -> g:\n  y: 1\nr -> g:\n  x: 2\nd -> l -> r:\n  z: 1\n").is_empty());
+  }
+
+  #[test]
+  fn nested_parents_are_found_from_inside() {
+    let found = lint("top:\n  a:\n    x: 1\n  b:\n    x: 2\n  c -> a -> b:\n    y: 1\n");
+
+    assert_eq!(messages(&found), vec!("`top.a` and `top.b` both have a `x` field"));
+  }
+
+  #[test]
+  fn levels() {
+    let code = "box:\n  size: 1\n  size: 2\n";
+
+    let mut lints = Lints::new();
+
+    lints.set(Lint::RedefinedField, Level::Allow);
+    assert!(lint_with(&lints, code).is_empty());
+
+    lints.set(Lint::RedefinedField, Level::Deny);
+    assert_eq!(lint_with(&lints, code)[0].severity, Severity::Error);
+
+    assert_eq!(lint(code)[0].severity, Severity::Warning);
+  }
+
+  #[test]
+  fn names_round_trip() {
+    for lint in Lint::ALL.iter() {
+      assert_eq!(Lint::from_name(lint.name()), Some(*lint));
+    }
+  }
+}
@@ -0,0 +1,8 @@
+#[allow(clippy::module_inception)]
+pub mod linter;
+
+use self::super::parser::*;
+use self::super::lexer::Pos;
+use self::super::source::Source;
+
+pub use self::linter::*;
@@ -1,6 +1,6 @@
-#[macro_use]
 pub mod error;
 pub mod source;
 pub mod lexer;
 pub mod parser;
-pub mod interpreter;
\ No newline at end of file
+pub mod interpreter;
+pub mod linter;
\ No newline at end of file
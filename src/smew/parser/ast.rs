@@ -9,6 +9,14 @@ pub enum StatementNode {
   Assignment(String, Expression),
   Return(Option<Rc<Expression>>),
   Record(String, Vec<Expression>, Vec<Statement>),
+  Function(String, Vec<String>, Rc<Vec<Statement>>),
+  Import(String, Option<String>),
+  If(Vec<(Expression, Vec<Statement>)>, Option<Vec<Statement>>),
+  For(Vec<String>, Expression, Vec<Statement>),
+  While(Expression, Vec<Statement>),
+  Break,
+  Continue,
+  Error, // stands in for a statement that failed to parse
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -38,6 +46,12 @@ pub enum ExpressionNode {
   Not(Rc<Expression>), // not
   Binary(Rc<Expression>, Operator, Rc<Expression>),
   Call(Rc<Expression>, Vec<Expression>),
+  List(Vec<Expression>),
+  Subscript(Rc<Expression>, Rc<Expression>), // xs[i]
+  Field(Rc<Expression>, String), // record.field
+  If(Rc<Expression>, Rc<Expression>, Rc<Expression>), // a if condition else b
+  Interpolation(Vec<Expression>), // "text {expression} text"
+  Invoke(Rc<Expression>), // field!
   EOF,
 }
 
@@ -54,17 +68,61 @@ impl Expression {
       pos,
     }
   }
+
+  // walks the expression and everything in it
+  pub fn visit(&self, f: &mut dyn FnMut(&Expression)) {
+    use self::ExpressionNode::*;
+
+    f(self);
+
+    match self.node {
+      Neg(ref a) | Not(ref a) | Invoke(ref a) | Field(ref a, _) => a.visit(f),
+
+      Binary(ref a, _, ref b) | Subscript(ref a, ref b) => {
+        a.visit(f);
+        b.visit(f)
+      },
+
+      Call(ref callee, ref args) => {
+        callee.visit(f);
+        args.iter().for_each(|arg| arg.visit(f))
+      },
+
+      List(ref items) | Interpolation(ref items) => items.iter().for_each(|item| item.visit(f)),
+
+      If(ref condition, ref then, ref otherwise) => {
+        condition.visit(f);
+        then.visit(f);
+        otherwise.visit(f)
+      },
+
+      _ => (),
+    }
+  }
+
+  // whether evaluating the expression could do anything besides produce its value
+  pub fn calls(&self) -> bool {
+    let mut found = false;
+
+    self.visit(&mut |expression| {
+      if let ExpressionNode::Call(..) | ExpressionNode::Invoke(..) = expression.node {
+        found = true
+      }
+    });
+
+    found
+  }
 }
 
 
 
 #[derive(Debug, Clone, PartialEq)]
 pub enum Operator {
-  Add, Sub, Mul, Div, Mod, Pow, Concat, Eq, Lt, Gt, NEq, LtEq, GtEq, Or, And, Index,
+  Add, Sub, Mul, Div, IntDiv, Mod, Pow, Concat, Eq, Lt, Gt, NEq, LtEq, GtEq, Or, And,
 }
 
 impl Operator {
-  pub fn from_str(operator: &str) -> Option<(Operator, u8)> {
+  pub fn from_lexeme(operator: &str) -> Option<(Operator, u8)> {
     use self::Operator::*;
 
     let op_prec = match operator {
@@ -81,26 +139,31 @@ impl Operator {
       "++"  => (Concat, 2),
       "*"   => (Mul,    3),
       "/"   => (Div,    3),
+      "//"  => (IntDiv, 3),
       "%"   => (Mod,    3),
       "^"   => (Pow,    4),
-      "."   => (Index,  5),
       _     => return None,
     };
 
     Some(op_prec)
   }
 
+  // `2 ^ 3 ^ 2` is `2 ^ 9`, everything else groups from the left
+  pub fn is_right_associative(&self) -> bool {
+    *self == Operator::Pow
+  }
+
   pub fn as_str(&self) -> &str {
     use self::Operator::*;
 
     match *self {
-      Index  => ".",
       Add    => "+",
       Sub    => "-",
       Concat => "++",
       Pow    => "^",
       Mul    => "*",
       Div    => "/",
+      IntDiv => "//",
       Mod    => "%",
       Eq     => "==",
       Lt     => "<",
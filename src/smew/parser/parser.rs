@@ -1,5 +1,5 @@
 use super::*;
-use super::super::error::Response::Wrong;
+use super::super::error::{ SmewError, ErrorKind };
 
 use std::rc::Rc;
 
@@ -10,6 +10,13 @@ pub struct Parser<'p> {
 
   indent_standard: usize,
   indent:          usize,
+
+  // how many record or function bodies we're inside, `return` is only valid in one
+  bodies: usize,
+  // the same for loops in the current body, where `break` and `continue` are valid
+  loops:  usize,
+
+  errors: Vec<SmewError>,
 }
 
 impl<'p> Parser<'p> {
@@ -21,24 +28,91 @@ impl<'p> Parser<'p> {
 
       indent_standard: 0,
       indent: 0,
+
+      bodies: 0,
+      loops:  0,
+
+      errors: Vec::new(),
     }
   }
 
 
 
-  pub fn parse(&mut self) -> Result<Vec<Statement>, ()> {
+  pub fn parse(&mut self) -> Result<Vec<Statement>, Vec<SmewError>> {
+    let (ast, errors) = self.parse_partial();
+
+    if errors.is_empty() {
+      Ok(ast)
+    } else {
+      Err(errors)
+    }
+  }
+
+  // parses as much as possible, broken statements turn into `StatementNode::Error`
+  pub fn parse_partial(&mut self) -> (Vec<Statement>, Vec<SmewError>) {
     let mut ast = Vec::new();
 
     while self.remaining() > 0 {
-      ast.push(self.parse_statement()?)
+      ast.push(self.parse_statement_recovering())
+    }
+
+    (ast, self.errors.drain(..).collect())
+  }
+
+
+
+  fn parse_statement_recovering(&mut self) -> Statement {
+    self.next_newline().ok();
+
+    let position = self.current_position();
+    let indent   = self.get_indent();
+    let start    = self.index;
+    let state    = (self.indent, self.bodies, self.loops);
+
+    match self.parse_statement() {
+      Ok(statement) => statement,
+
+      Err(error) => {
+        let end = (self.index + 1).min(self.tokens.len());
+
+        // the lexer has already complained about its error tokens, once is enough
+        if !self.tokens[start.min(end) .. end].iter().any(|token| token.token_type == TokenType::Error) {
+          self.errors.push(error)
+        }
+
+        let (indent_level, bodies, loops) = state;
+
+        self.indent = indent_level;
+        self.bodies = bodies;
+        self.loops  = loops;
+
+        self.synchronize(indent);
+
+        Statement::new(StatementNode::Error, position)
+      },
     }
+  }
 
-    Ok(ast)
+  // skips the rest of a broken statement, along with anything indented under it
+  fn synchronize(&mut self, indent: usize) {
+    loop {
+      while self.remaining() > 0 && self.current_lexeme() != "\n" {
+        self.index += 1
+      }
+
+      while self.remaining() > 0 && self.current_lexeme() == "\n" {
+        self.index += 1
+      }
+
+      if self.remaining() == 0 || self.get_indent() <= indent {
+        break
+      }
+    }
   }
 
 
 
-  fn parse_statement(&mut self) -> Result<Statement, ()> {
+  fn parse_statement(&mut self) -> Result<Statement, SmewError> {
     use self::TokenType::*;
 
     while self.current_type() == EOL && self.remaining() != 0 {
@@ -63,24 +137,7 @@ impl<'p> Parser<'p> {
         if self.current_lexeme() == ":" {
           self.next()?;
 
-          let body = if self.current_lexeme() == "\n" {
-            self.new_line()?;
-            self.next_newline()?;
-
-            self.parse_body()?
-          } else {
-            let expression = self.parse_expression()?;
-            let position   = expression.pos.clone();
-
-            vec!(
-              Statement::new(
-                StatementNode::Expression(
-                  expression
-                ),
-                position
-              )
-            )
-          };
+          let body = self.parse_returnable_block()?;
 
           let record = Statement::new(
             StatementNode::Record(
@@ -113,6 +170,218 @@ impl<'p> Parser<'p> {
         }
       },
       
+      Keyword => match self.current_lexeme().as_str() {
+        "def" => {
+          self.next()?;
+
+          let name = self.eat_type(&Identifier)?;
+
+          self.eat_lexeme("(")?;
+
+          let mut params = Vec::new();
+
+          while self.current_lexeme() != ")" {
+            let position = self.current_position();
+            let param    = self.eat_type(&Identifier)?;
+
+            if params.contains(&param) {
+              return Err(
+                self.error(
+                  format!("duplicate parameter `{}`", param),
+                  position
+                )
+              )
+            }
+
+            params.push(param);
+
+            if self.current_lexeme() != ")" {
+              self.eat_lexeme(",")?;
+            }
+          }
+
+          self.eat_lexeme(")")?;
+          self.eat_lexeme(":")?;
+
+          let body = self.parse_returnable_block()?;
+
+          return Ok(
+            Statement::new(
+              StatementNode::Function(name, params, Rc::new(body)),
+              position
+            )
+          )
+        },
+
+        "import" => {
+          self.next()?;
+
+          let name = if self.current_type() == Str {
+            None
+          } else {
+            let name = self.eat_type(&Identifier)?;
+
+            self.eat_lexeme("from")?;
+
+            Some(name)
+          };
+
+          let path = self.eat_type(&Str)?;
+
+          Statement::new(
+            StatementNode::Import(path, name),
+            self.span_from(position)
+          )
+        },
+
+        "if" => {
+          let indent = self.get_indent();
+
+          self.next()?;
+
+          let condition = self.parse_expression()?;
+
+          self.eat_lexeme(":")?;
+
+          let mut branches  = vec!((condition, self.parse_block()?));
+          let mut otherwise = None;
+
+          loop {
+            self.next_newline()?;
+
+            if self.remaining() == 0 || self.get_indent() != indent {
+              break
+            }
+
+            match self.current_lexeme().as_str() {
+              "elif" => {
+                self.next()?;
+
+                let condition = self.parse_expression()?;
+
+                self.eat_lexeme(":")?;
+
+                branches.push((condition, self.parse_block()?))
+              },
+
+              "else" => {
+                self.next()?;
+                self.eat_lexeme(":")?;
+
+                otherwise = Some(self.parse_block()?);
+
+                break
+              },
+
+              _ => break,
+            }
+          }
+
+          return Ok(
+            Statement::new(
+              StatementNode::If(branches, otherwise),
+              position
+            )
+          )
+        },
+
+        "for" => {
+          self.next()?;
+
+          let mut names = vec!(self.eat_type(&Identifier)?);
+
+          if self.current_lexeme() == "," {
+            self.next()?;
+
+            names.push(self.eat_type(&Identifier)?)
+          }
+
+          self.eat_lexeme("in")?;
+
+          let iterable = self.parse_expression()?;
+
+          self.eat_lexeme(":")?;
+
+          let body = self.parse_loop_block()?;
+
+          return Ok(
+            Statement::new(
+              StatementNode::For(names, iterable, body),
+              position
+            )
+          )
+        },
+
+        "while" => {
+          self.next()?;
+
+          let condition = self.parse_expression()?;
+
+          self.eat_lexeme(":")?;
+
+          let body = self.parse_loop_block()?;
+
+          return Ok(
+            Statement::new(
+              StatementNode::While(condition, body),
+              position
+            )
+          )
+        },
+
+        keyword @ "break" | keyword @ "continue" => {
+          if self.loops == 0 {
+            return Err(
+              self.error(
+                format!("can't {} outside of a loop", keyword),
+                self.current_position()
+              )
+            )
+          }
+
+          let node = if keyword == "break" {
+            StatementNode::Break
+          } else {
+            StatementNode::Continue
+          };
+
+          self.next()?;
+
+          Statement::new(node, position)
+        },
+
+        "return" => {
+          if self.bodies == 0 {
+            return Err(
+              self.error(
+                "can't return outside of a record or function",
+                self.current_position()
+              )
+            )
+          }
+
+          self.next()?;
+
+          let value = if self.current_lexeme() == "\n" || self.remaining() == 0 {
+            None
+          } else {
+            Some(Rc::new(self.parse_expression()?))
+          };
+
+          Statement::new(
+            StatementNode::Return(value),
+            self.span_from(position)
+          )
+        },
+
+        ref keyword => return Err(
+          self.error(
+            format!("unexpected keyword `{}`", keyword),
+            self.current_position()
+          )
+        )
+      },
+
       _ => {
         let expression = self.parse_expression()?;
         let position   = expression.pos.clone();
@@ -131,18 +400,68 @@ impl<'p> Parser<'p> {
 
 
 
-  fn parse_body(&mut self) -> Result<Vec<Statement>, ()> {
+  // a block that `return` may leave, as opposed to the branches of an `if`
+  fn parse_returnable_block(&mut self) -> Result<Vec<Statement>, SmewError> {
+    let loops = self.loops;
+
+    self.bodies += 1;
+    self.loops   = 0;
+
+    let body = self.parse_block();
+
+    self.bodies -= 1;
+    self.loops   = loops;
+
+    body
+  }
+
+  fn parse_loop_block(&mut self) -> Result<Vec<Statement>, SmewError> {
+    self.loops += 1;
+
+    let body = self.parse_block();
+
+    self.loops -= 1;
+
+    body
+  }
+
+  // what follows a `:`, either an indented body or a single inline expression
+  fn parse_block(&mut self) -> Result<Vec<Statement>, SmewError> {
+    if self.current_lexeme() == "\n" {
+      self.new_line()?;
+      self.next_newline()?;
+
+      self.parse_body()
+    } else {
+      let expression = self.parse_expression()?;
+      let position   = expression.pos.clone();
+
+      Ok(
+        vec!(
+          Statement::new(
+            StatementNode::Expression(
+              expression
+            ),
+            position
+          )
+        )
+      )
+    }
+  }
+
+
+
+  fn parse_body(&mut self) -> Result<Vec<Statement>, SmewError> {
     let backup_indent = self.indent;
     self.indent       = self.get_indent();
 
     if self.indent_standard == 0 {
       self.indent_standard = self.indent
     } else {
-      if self.indent % self.indent_standard != 0 {
+      if !self.indent.is_multiple_of(self.indent_standard) {
         return Err(
-          response!(
-            Wrong(format!("found inconsistently indented token")),
-            self.source.file,
+          self.error(
+            "found inconsistently indented token",
             self.current_position()
           )
         )
@@ -152,7 +471,7 @@ impl<'p> Parser<'p> {
     let mut stack = Vec::new();
 
     while !self.is_dedent() && self.remaining() > 0 {
-      let statement = self.parse_statement()?;
+      let statement = self.parse_statement_recovering();
 
       self.next_newline()?;
 
@@ -166,19 +485,40 @@ impl<'p> Parser<'p> {
 
 
 
-  fn parse_expression(&mut self) -> Result<Expression, ()> {
+  fn parse_expression(&mut self) -> Result<Expression, SmewError> {
     let atom = self.parse_atom()?;
 
-    if self.current_type() == TokenType::Operator {
-      self.parse_binary(atom)
+    let expression = if self.current_type() == TokenType::Operator {
+      self.parse_binary(atom)?
     } else {
-      Ok(atom)
+      atom
+    };
+
+    // `a if condition else b`
+    if self.current_type() == TokenType::Keyword && self.current_lexeme() == "if" {
+      self.next()?;
+
+      let condition = self.parse_expression()?;
+
+      self.eat_lexeme("else")?;
+
+      let otherwise = self.parse_expression()?;
+      let position  = expression.pos.clone();
+
+      return Ok(
+        Expression::new(
+          ExpressionNode::If(Rc::new(condition), Rc::new(expression), Rc::new(otherwise)),
+          self.span_from(position)
+        )
+      )
     }
+
+    Ok(expression)
   }
 
 
 
-  fn parse_atom(&mut self) -> Result<Expression, ()> {
+  fn parse_atom(&mut self) -> Result<Expression, SmewError> {
     use self::TokenType::*;
 
     if self.remaining() == 0 {
@@ -203,6 +543,12 @@ impl<'p> Parser<'p> {
           position
         ),
 
+        Template => {
+          let raw = self.eat()?;
+
+          self.parse_template(&raw, position)?
+        },
+
         Bool => Expression::new(
           ExpressionNode::Bool(self.eat()? == "true"),
           position
@@ -239,18 +585,40 @@ impl<'p> Parser<'p> {
           },
 
           ref op => return Err(
-            response!(
-              Wrong(format!("unexpected operator `{}`", op)),
-              self.source.file,
+            self.error(
+              format!("unexpected operator `{}`", op),
               self.current_position()
             )
           )
         },
 
+        Symbol if self.current_lexeme() == "[" => {
+          self.next()?;
+          self.next_newline()?;
+
+          let mut items = Vec::new();
+
+          while self.current_lexeme() != "]" && self.remaining() > 0 {
+            items.push(self.parse_expression()?);
+            self.next_newline()?;
+
+            if self.current_lexeme() != "]" {
+              self.eat_lexeme(",")?;
+              self.next_newline()?;
+            }
+          }
+
+          self.eat_lexeme("]")?;
+
+          Expression::new(
+            ExpressionNode::List(items),
+            self.span_from(position)
+          )
+        },
+
         ref token_type => return Err(
-          response!(
-            Wrong(format!("unexpected token `{}`", token_type)),
-            self.source.file,
+          self.error(
+            format!("unexpected token `{}`", token_type),
             self.current_position()
           )
         )
@@ -264,132 +632,258 @@ impl<'p> Parser<'p> {
     }
   }
 
-  fn parse_postfix(&mut self, expression: Expression) -> Result<Expression, ()> {
-    let backup_index = self.index;
+  // `"area is {width * height}"`, where each embedded expression is lexed and parsed on its own
+  fn parse_template(&mut self, raw: &str, position: Pos) -> Result<Expression, SmewError> {
+    let mut parts = Vec::new();
+
+    for piece in pieces(raw) {
+      let (offset, code) = match piece {
+        Piece::Text(text)         => {
+          parts.push(Expression::new(ExpressionNode::Str(text), position.clone()));
+          continue
+        },
+
+        Piece::Code(offset, code) => (offset, code),
+      };
+
+      // padded out so every token lands where it really is in the source
+      let column = (position.1).0 + offset;
+      let padded = format!("{}{}{}\n", "\n".repeat((position.0).0 - 1), " ".repeat(column), code);
+
+      let brace = Pos(position.0.clone(), (column, column + code.chars().count() + 1));
 
+      if code.trim().is_empty() {
+        return Err(
+          self.error(
+            "expected an expression between `{` and `}`",
+            brace
+          )
+        )
+      }
+
+      let (tokens, mut errors) = Lexer::default(padded.chars().collect(), self.source).tokenize();
+
+      if !errors.is_empty() {
+        return Err(errors.remove(0))
+      }
+
+      let tokens = tokens.into_iter().skip_while(|token| token.token_type == TokenType::EOL).collect();
+
+      let mut parser = Parser::new(tokens, self.source);
+
+      let expression = parser.parse_expression()?;
+
+      if parser.current_type() != TokenType::EOL {
+        return Err(
+          self.error(
+            format!("unexpected `{}` in string", parser.current_lexeme()),
+            parser.current_position()
+          )
+        )
+      }
+
+      parts.push(expression)
+    }
+
+    Ok(
+      Expression::new(
+        ExpressionNode::Interpolation(parts),
+        position
+      )
+    )
+  }
+
+  fn parse_postfix(&mut self, expression: Expression) -> Result<Expression, SmewError> {
     if self.remaining() == 0 {
       return Ok(expression)
     }
 
     match self.current_type() {
-      ref current => {
-        if let TokenType::Symbol = current {
-          if self.current_lexeme() == "(" {
-            self.next()?;
-            self.next_newline()?;;
+      TokenType::Symbol => {
+        if self.current_lexeme() == "(" {
+          self.next()?;
+          self.next_newline()?;
 
-            let mut args = Vec::new();
+          let mut args = Vec::new();
 
-            if ![TokenType::Operator, TokenType::Keyword].contains(&self.current_type()) {
-              while !["\n", ")"].contains(&self.current_lexeme().as_str()) {
-                args.push(self.parse_expression()?);
+          if ![TokenType::Operator, TokenType::Keyword].contains(&self.current_type()) {
+            while !["\n", ")"].contains(&self.current_lexeme().as_str()) {
+              args.push(self.parse_expression()?);
 
-                if !["\n", ")"].contains(&self.current_lexeme().as_str()) && self.remaining() > 0 {
-                  self.eat_lexeme(",")?;
-                  self.next_newline()?;
-                }
+              if !["\n", ")"].contains(&self.current_lexeme().as_str()) && self.remaining() > 0 {
+                self.eat_lexeme(",")?;
+                self.next_newline()?;
               }
             }
+          }
 
-            self.next_newline()?;
-            self.eat_lexeme(")")?;
+          self.next_newline()?;
+          self.eat_lexeme(")")?;
 
-            let position = expression.pos.clone();
+          let position = expression.pos.clone();
 
-            return Ok(
-              Expression::new(
-                ExpressionNode::Call(
-                  Rc::new(expression),
-                  args,
-                ),
-                self.span_from(position)
-              )
-            )
-          }
+          let call = Expression::new(
+            ExpressionNode::Call(
+              Rc::new(expression),
+              args,
+            ),
+            self.span_from(position)
+          );
+
+          return self.parse_postfix(call)
+        }
+
+        if self.current_lexeme() == "!" {
+          let position = expression.pos.clone();
+          let bang     = self.current_position();
+
+          self.next()?;
+
+          let invoke = Expression::new(
+            ExpressionNode::Invoke(
+              Rc::new(expression)
+            ),
+            Pos(position.0, ((position.1).0, (bang.1).1))
+          );
+
+          return self.parse_postfix(invoke)
+        }
+
+        if self.current_lexeme() == "[" {
+          self.next()?;
+          self.next_newline()?;
+
+          let index = self.parse_expression()?;
+
+          self.next_newline()?;
+          self.eat_lexeme("]")?;
+
+          let position = expression.pos.clone();
+
+          let subscript = Expression::new(
+            ExpressionNode::Subscript(
+              Rc::new(expression),
+              Rc::new(index),
+            ),
+            self.span_from(position)
+          );
+
+          return self.parse_postfix(subscript)
         }
 
         Ok(expression)
       },
 
+      // `record.field` binds like the rest, so `path.points[0]` and `util.double(3)` apply to the field
+      TokenType::Operator if self.current_lexeme() == "." => {
+        self.next()?;
+
+        let position = expression.pos.clone();
+        let name_pos = self.current_position();
+
+        if self.current_type() != TokenType::Identifier {
+          return Err(
+            self.error(
+              format!("expected a field name after `.`, found `{}`", self.current_lexeme()),
+              name_pos
+            )
+          )
+        }
+
+        let name = self.eat()?;
+
+        let field = Expression::new(
+          ExpressionNode::Field(
+            Rc::new(expression),
+            name
+          ),
+          Pos(position.0, ((position.1).0, (name_pos.1).1))
+        );
+
+        self.parse_postfix(field)
+      },
+
       _ => Ok(expression)
     }
   }
 
 
 
-  fn parse_binary(&mut self, left: Expression) -> Result<Expression, ()> {
-    let left_position = left.pos.clone();
-
+  fn parse_binary(&mut self, left: Expression) -> Result<Expression, SmewError> {
     let mut expression_stack = vec!(left);
-    let mut operator_stack   = vec!(Operator::from_str(&self.eat()?).unwrap());
-
-    expression_stack.push(self.parse_atom()?);
+    let mut operator_stack   = Vec::new();
 
-    while operator_stack.len() > 0 {
-      while self.current_type() == TokenType::Operator {
-        let position               = self.current_position();
-        let (operator, precedence) = Operator::from_str(&self.eat()?).unwrap();
+    while self.current_type() == TokenType::Operator {
+      let position = self.current_position();
+      let lexeme   = self.eat()?;
 
-        if precedence < operator_stack.last().unwrap().1 {
-          let right = expression_stack.pop().unwrap();
-          let left  = expression_stack.pop().unwrap();
-
-          expression_stack.push(
-            Expression::new(
-              ExpressionNode::Binary(Rc::new(left), operator_stack.pop().unwrap().0, Rc::new(right)),
-              self.current_position(),
-            )
-          );
+      let (operator, precedence) = match Operator::from_lexeme(&lexeme) {
+        Some(op_prec) => op_prec,
+        None          => return Err(
+          self.error(
+            format!("unexpected operator `{}`", lexeme),
+            position
+          )
+        )
+      };
 
-          if self.remaining() > 0 {
-            expression_stack.push(self.parse_atom()?);
-            operator_stack.push((operator, precedence))
-          } else {
-            return Err(
-              response!(
-                Wrong("reached EOF in operation"),
-                self.source.file,
-                position
-              )
-            )
-          }
+      while let Some(&(_, top_precedence)) = operator_stack.last() {
+        if top_precedence > precedence || top_precedence == precedence && !operator.is_right_associative() {
+          Self::reduce_binary(&mut expression_stack, &mut operator_stack)
         } else {
-          expression_stack.push(self.parse_atom()?);
-          operator_stack.push((operator, precedence))
+          break
         }
       }
 
-      let right = expression_stack.pop().unwrap();
-      let left  = expression_stack.pop().unwrap();
-
-      expression_stack.push(
-        Expression::new(
-          ExpressionNode::Binary(Rc::new(left), operator_stack.pop().unwrap().0, Rc::new(right)),
-          self.current_position(),
+      if self.remaining() == 0 {
+        return Err(
+          self.error(
+            "reached EOF in operation",
+            position
+          )
         )
-      );
+      }
+
+      expression_stack.push(self.parse_atom()?);
+      operator_stack.push((operator, precedence))
     }
 
-    let expression = expression_stack.pop().unwrap();
+    while !operator_stack.is_empty() {
+      Self::reduce_binary(&mut expression_stack, &mut operator_stack)
+    }
 
-    Ok(
+    Ok(expression_stack.pop().unwrap())
+  }
+
+  fn reduce_binary(expression_stack: &mut Vec<Expression>, operator_stack: &mut Vec<(Operator, u8)>) {
+    let right = expression_stack.pop().unwrap();
+    let left  = expression_stack.pop().unwrap();
+
+    let (operator, _) = operator_stack.pop().unwrap();
+
+    let position = if (left.pos.0).0 == (right.pos.0).0 {
+      Pos(left.pos.0.clone(), ((left.pos.1).0, (right.pos.1).1))
+    } else {
+      left.pos.clone()
+    };
+
+    expression_stack.push(
       Expression::new(
-        expression.node,
-        self.span_from(left_position)
+        ExpressionNode::Binary(Rc::new(left), operator, Rc::new(right)),
+        position,
       )
     )
   }
 
 
 
-  fn new_line(&mut self) -> Result<(), ()> {
+  fn new_line(&mut self) -> Result<(), SmewError> {
     if self.remaining() > 0 {
       match self.current_lexeme().as_str() {
         "\n" => self.next(),
         _    => Err(
-          response!(
-            Wrong(format!("expected new line found: `{}`", self.current_lexeme())),
-            self.source.file,
+          self.error(
+            format!("expected new line found: `{}`", self.current_lexeme()),
             self.current_position()
           )
         )
@@ -401,7 +895,7 @@ impl<'p> Parser<'p> {
 
 
 
-  fn next_newline(&mut self) -> Result<(), ()> {
+  fn next_newline(&mut self) -> Result<(), SmewError> {
     while self.current_lexeme() == "\n" && self.remaining() > 0 {
       self.next()?
     }
@@ -421,29 +915,25 @@ impl<'p> Parser<'p> {
 
 
 
-  fn next(&mut self) -> Result<(), ()> {
+  fn next(&mut self) -> Result<(), SmewError> {
     if self.index <= self.tokens.len() {
-      use backtrace::Backtrace;
-
-      let bt = Backtrace::new();
-
-      if self.current_lexeme() == "fazoo" {
-        println!("{:?}", bt);
-      }
       self.index += 1;
 
       Ok(())
     } else {
       Err(
-        response!(
-          Wrong("moving outside token stack"),
-          self.source.file,
+        self.error(
+          "moving outside token stack",
           self.current_position()
         )
       )
     }
   }
 
+  fn error<T: Into<String>>(&self, message: T, pos: Pos) -> SmewError {
+    SmewError::at(ErrorKind::Parser, message, &self.source.file, pos)
+  }
+
   fn remaining(&self) -> usize {
     self.tokens.len().saturating_sub(self.index)
   }
@@ -472,14 +962,14 @@ impl<'p> Parser<'p> {
     }
   }
 
-  fn eat(&mut self) -> Result<String, ()> {
+  fn eat(&mut self) -> Result<String, SmewError> {
     let lexeme = self.current().lexeme;
     self.next()?;
 
     Ok(lexeme)
   }
 
-  fn eat_lexeme(&mut self, lexeme: &str) -> Result<String, ()> {
+  fn eat_lexeme(&mut self, lexeme: &str) -> Result<String, SmewError> {
     if self.current_lexeme() == lexeme {
       let lexeme = self.current().lexeme;
       self.next()?;
@@ -487,16 +977,15 @@ impl<'p> Parser<'p> {
       Ok(lexeme)
     } else {
       Err(
-        response!(
-          Wrong(format!("expected `{}` but found `{}`", lexeme, self.current_lexeme())),
-          self.source.file,
+        self.error(
+          format!("expected `{}` but found `{}`", lexeme, self.current_lexeme()),
           self.current_position()
         )
       )
     }
   }
 
-  fn eat_type(&mut self, token_type: &TokenType) -> Result<String, ()> {
+  fn eat_type(&mut self, token_type: &TokenType) -> Result<String, SmewError> {
     if self.current_type() == *token_type {
       let lexeme = self.current().lexeme.clone();
       self.next()?;
@@ -504,9 +993,8 @@ impl<'p> Parser<'p> {
       Ok(lexeme)
     } else {
       Err(
-        response!(
-          Wrong(format!("expected `{}` but found `{}`", token_type, self.current_type())),
-          self.source.file,
+        self.error(
+          format!("expected `{}` but found `{}`", token_type, self.current_type()),
           self.current_position()
         )
       )
@@ -520,30 +1008,46 @@ impl<'p> Parser<'p> {
   fn current_type(&self) -> TokenType {
     self.current().token_type
   }
+}
 
-  fn expect_type(&self, token_type: TokenType) -> Result<(), ()> {
-    if self.current_type() == token_type {
-      Ok(())
-    } else {
-      Err(
-        response!(
-          Wrong(format!("expected `{}` but found `{}`", token_type, self.current_type())),
-          self.source.file
-        )
-      )
-    }
+
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn parse(code: &str) -> (Vec<Statement>, Vec<SmewError>) {
+    let source = Source::from("<test>", code.lines().map(|x| x.to_string()).collect());
+
+    let (tokens, errors) = Lexer::default(source.chars(), &source).tokenize();
+
+    assert!(errors.is_empty());
+
+    Parser::new(tokens, &source).parse_partial()
   }
 
-  fn expect_lexeme(&self, lexeme: &str) -> Result<(), ()> {
-    if self.current_lexeme() == lexeme {
-      Ok(())
-    } else {
-      Err(
-        response!(
-          Wrong(format!("expected `{}` but found `{}`", lexeme, self.current_lexeme())),
-          self.source.file
-        )
-      )
+  fn kinds(body: &[Statement]) -> Vec<&'static str> {
+    body.iter().map(|statement| match statement.node {
+      StatementNode::Error          => "error",
+      StatementNode::Assignment(..) => "assignment",
+      StatementNode::Record(..)     => "record",
+      StatementNode::Expression(..) => "expression",
+      _                             => "other",
+    }).collect()
+  }
+
+  #[test]
+  fn every_broken_statement_is_reported() {
+    let (ast, errors) = parse("a = )\nb = 1\nbox:\n  c = ]\n  d: 2\ne = 1 +\nprint(b)\n");
+
+    assert_eq!(errors.iter().map(|error| (error.pos.as_ref().unwrap().0).0).collect::<Vec<usize>>(), vec!(1, 4, 6));
+
+    // the rest of the script still parses around them
+    assert_eq!(kinds(&ast), vec!("error", "assignment", "record", "error", "expression"));
+
+    match ast[2].node {
+      StatementNode::Record(_, _, ref body) => assert_eq!(kinds(body), vec!("error", "record")),
+      ref other                             => panic!("parsed {:?}", other),
     }
   }
 }
\ No newline at end of file
@@ -1,11 +1,12 @@
 use std::fs::File;
+use std::io;
 use std::io::prelude::*;
 
 use std::fmt;
 
 use colored::Colorize;
 
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct FilePath(pub String);
 
 impl fmt::Display for FilePath {
@@ -23,16 +24,26 @@ pub struct Source {
 }
 
 impl Source {
-  pub fn new(path: String) -> Self {
-    let mut source  = File::open(path.as_str()).unwrap();
+  pub fn new(path: String) -> io::Result<Self> {
+    let mut source  = File::open(path.as_str())?;
     let mut content = String::new();
 
-    source.read_to_string(&mut content).unwrap();
+    source.read_to_string(&mut content)?;
 
-    Source {
-      file:  FilePath(path),
-      lines: content.lines().map(|x| x.to_string()).collect()
-    }
+    Ok(
+      Source {
+        file:  FilePath(path),
+        lines: content.lines().map(|x| x.to_string()).collect()
+      }
+    )
+  }
+
+  pub fn from_stdin() -> io::Result<Self> {
+    let mut content = String::new();
+
+    io::stdin().read_to_string(&mut content)?;
+
+    Ok(Self::from("<stdin>", content.lines().map(|x| x.to_string()).collect()))
   }
 
   pub fn from(path: &str, lines: Vec<String>) -> Self {
@@ -41,4 +52,17 @@ impl Source {
       lines,
     }
   }
-}
\ No newline at end of file
+
+  // the line numbered from one, the last line for positions past the end, nothing for an empty source
+  pub fn line(&self, number: usize) -> String {
+    self.lines.get(number.saturating_sub(1)).or(self.lines.last()).cloned().unwrap_or_default()
+  }
+
+  // the characters handed to the lexer, kept in sync with `lines` so positions line up
+  pub fn chars(&self) -> Vec<char> {
+    let mut content = self.lines.join("\n");
+    content.push('\n');
+
+    content.chars().collect()
+  }
+}